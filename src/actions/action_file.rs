@@ -1,22 +1,54 @@
-use super::{validator, Page, Settings, SettingsAccumulator, ValidationError};
+use super::{validator, Entry, Page, Settings, SettingsAccumulator, ValidationError};
+use failure::Error;
+use schemars::JsonSchema;
 use std::collections::BTreeMap;
-use crate::AppOptions;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ActionFile {
     #[serde(rename = "global", default = "Settings::default")]
     global_settings: Settings,
     pages: BTreeMap<String, Page>, // BTreeMap so order is preserved; helps with validation logic, etc.
+    /// Theme files that were read when loading the themes.
+    #[serde(skip)]
+    theme_files: Vec<PathBuf>,
 }
 
 impl ActionFile {
+    /// An action file without any pages, and the default global settings.
+    pub fn new() -> ActionFile {
+        ActionFile {
+            global_settings: Settings::default(),
+            pages: BTreeMap::new(),
+            theme_files: Vec::new(),
+        }
+    }
+
+    /// Reads an action file, and loads the themes it uses.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ActionFile, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)?;
+        let mut actions: ActionFile = serde_yaml::from_str(&data)?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        actions.resolve_themes(directory)?;
+        Ok(actions)
+    }
+
+    /// Adds a page, replacing any page that already has the name.
+    pub fn with_page<S: Into<String>>(mut self, name: S, page: Page) -> ActionFile {
+        self.pages.insert(name.into(), page);
+        self
+    }
+
     pub fn pages_with_names(&self) -> impl Iterator<Item = (&Page, &str)> {
         self.pages.iter().map(|(name, page)| (page, name.as_ref()))
     }
 
-    pub fn validate(&self, options: &AppOptions) -> Result<(), Vec<ValidationError>> {
-        validator::validate(self, &options.start_page)
+    /// Validates the action file, when starting on the given page.
+    pub fn validate(&self, start_page: &str) -> Result<(), Vec<ValidationError>> {
+        validator::validate(self, start_page)
     }
 
     pub fn has_page(&self, page_name: &str) -> bool {
@@ -27,39 +59,125 @@ impl ActionFile {
         &self.pages[page_name]
     }
 
+    /// Finds the entry with the given id, on any page, along with the name of its page.
+    pub fn entry_with_id(&self, id: &str) -> Option<(&Entry, &str)> {
+        self.pages_with_names()
+            .filter_map(|(page, name)| page.entry_with_id(id).map(|entry| (entry, name)))
+            .next()
+    }
+
+    /// Loads every theme used in the settings of the action file. Theme files are looked up
+    /// relative to the given directory, which should be the one the action file is in.
+    pub fn resolve_themes(&mut self, directory: &Path) -> Result<(), Error> {
+        let mut files = Vec::new();
+        self.global_settings.resolve_theme(directory, &mut files)?;
+        for page in self.pages.values_mut() {
+            if let Some(settings) = page.settings_mut() {
+                settings.resolve_theme(directory, &mut files)?;
+            }
+            for group in page.groups_mut() {
+                if let Some(settings) = group.settings_mut() {
+                    settings.resolve_theme(directory, &mut files)?;
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+        self.theme_files = files;
+        Ok(())
+    }
+
+    /// The theme files that the action file uses, directly or through other themes. These are
+    /// known once the themes have been resolved.
+    pub fn theme_files(&self) -> &[PathBuf] {
+        &self.theme_files
+    }
+
     pub fn settings_accumulator(&self) -> SettingsAccumulator {
         SettingsAccumulator::from(&self.global_settings)
     }
 }
 
+impl Default for ActionFile {
+    fn default() -> ActionFile {
+        ActionFile::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     extern crate serde_yaml;
 
-    fn default_options() -> AppOptions {
-        AppOptions {
-            filename: Some(String::from("/dev/null")),
-            ignore_exit_status: false,
-            start_page: String::from("root"),
-            validate: false,
-            generate_completions: None,
-        }
-    }
-
     #[test]
     fn it_loads_minimal_yaml() {
-        let options = default_options();
         let actions: ActionFile =
             serde_yaml::from_str(include_str!("../../tests/fixtures/minimal.yml")).unwrap();
-        actions.validate(&options).unwrap();
+        actions.validate("root").unwrap();
     }
 
     #[test]
     fn it_loads_complex_yaml() {
-        let options = default_options();
         let actions: ActionFile =
             serde_yaml::from_str(include_str!("../../tests/fixtures/complex.yml")).unwrap();
-        actions.validate(&options).unwrap();
+        actions.validate("root").unwrap();
+    }
+
+    #[test]
+    fn it_builds_action_files_in_code() {
+        use crate::actions::{Command, Group, Return, RunMode};
+
+        let actions = ActionFile::new()
+            .with_page(
+                "root",
+                Page::new("Start").with_footer("Footer").with_group(
+                    Group::new().with_title("Pages").with_entry(
+                        Entry::new('o', "Other")
+                            .with_return(Return::OtherPage(String::from("other"))),
+                    ),
+                ),
+            )
+            .with_page(
+                "other",
+                Page::new("Other").with_group(
+                    Group::new().with_entry(
+                        Entry::new('l', "List files")
+                            .with_id("list")
+                            .with_command("ls")
+                            .with_mode(RunMode::Wait),
+                    ),
+                ),
+            );
+        actions.validate("root").unwrap();
+
+        let (entry, page) = actions.entry_with_id("list").unwrap();
+        assert_eq!(page, "other");
+        assert_eq!(entry.command(), &Command::ShellScript(String::from("ls")));
+        assert_eq!(entry.return_to(), &Return::Quit);
+        assert_eq!(actions.get_page("root").footer(), Some("Footer"));
+
+        let broken = actions.with_page(
+            "root",
+            Page::new("Start").with_group(Group::new().with_entry(
+                Entry::new('x', "Nowhere").with_return(Return::OtherPage(String::from("missing"))),
+            )),
+        );
+        assert!(broken.validate("root").is_err());
+    }
+
+    #[test]
+    fn it_generates_a_json_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(ActionFile)).unwrap();
+
+        assert_eq!(schema["required"], serde_json::json!(["pages"]));
+        for definition in &[
+            "Page", "Group", "Entry", "Settings", "Color", "Layout", "RunMode", "Return",
+        ] {
+            assert!(
+                schema["definitions"].get(definition).is_some(),
+                "Schema is missing definition for {}",
+                definition
+            );
+        }
     }
 }
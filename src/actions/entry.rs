@@ -1,17 +1,36 @@
 extern crate serde;
 
 use super::Color;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
 use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::{Serialize, Serializer};
 use std::fmt;
 
 /// Represents a single entry in the action file. This entry is something a user can select when
 /// they are on the page that contains this entry.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Entry {
     /// The title of the entry. Will be rendered in the menu.
     title: String,
 
+    /// Optional icon, like a Nerd Font glyph, to show with the entry. Placed where `{icon}` is in
+    /// the entry format.
+    icon: Option<String>,
+
+    /// Optional longer explanation of the entry. Shown in the help overlay, which is opened with
+    /// the ? key.
+    description: Option<String>,
+
+    /// Optional identifier of the entry, unique in the whole action file. Lets the entry be
+    /// referred to without knowing its shortcut, e.g. from the command line.
+    id: Option<String>,
+
+    /// Optional text to print when the entry is picked with --print-choice. Defaults to the title.
+    value: Option<String>,
+
     /// The character used to activate this shortcut; e.g. 'c' to activate when user presses the C
     /// key on their keyboard, or 'C' to activate when user presses Shift+C keys.
     shortcut: char,
@@ -35,7 +54,7 @@ pub struct Entry {
 }
 
 /// Represents something to execute when an Entry is selected.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
 #[serde(deny_unknown_fields, untagged)]
 pub enum Command {
     /// Run no command and instead only act on the "Return" setting.
@@ -73,6 +92,12 @@ pub enum Action {
     /// Run a Command in the background and return to tydra.
     RunBackground { command: Command, return_to: Return },
 
+    /// Put the text on the clipboard and return to tydra.
+    Copy { text: String, return_to: Return },
+
+    /// Exit tydra, and print the text on stdout.
+    Type { text: String },
+
     /// Exit tydra.
     Exit,
 
@@ -82,12 +107,25 @@ pub enum Action {
 
     /// Place tydra in the background (^Z)
     Pause,
+
+    /// Render the menu again for the new size of the terminal.
+    Resize,
+
+    /// Load the action file again, as it has been changed on disk.
+    Reload,
+
+    /// Show the help overlay for the current page.
+    Help,
+
+    /// Run the entry that was run last again.
+    Repeat,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum RunMode {
     /// Runs the command and then returns to tydra as soon as it has finished.
+    #[default]
     Normal,
 
     /// Display a "Press enter to continue" prompt after the command has finished before
@@ -100,16 +138,88 @@ pub enum RunMode {
     /// Fork and exec the command with no terminal devices still attached. This is useful for
     /// starting GUI programs.
     Background,
+
+    /// Put the value of the entry, or the text of its command, on the clipboard instead of
+    /// running anything.
+    Copy,
+
+    /// Exit and print the value of the entry, or the text of its command, on stdout instead of
+    /// running anything. Shell widgets can insert it into the command line.
+    Type,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum Return {
+    #[default]
     Quit,
     SamePage,
     OtherPage(String),
 }
 
 impl Entry {
+    /// An entry without a command, that closes the menu when it is picked. Use the `with_`
+    /// methods to change that.
+    pub fn new<S: Into<String>>(shortcut: char, title: S) -> Entry {
+        Entry {
+            title: title.into(),
+            icon: None,
+            description: None,
+            id: None,
+            value: None,
+            shortcut,
+            command: Command::None,
+            shortcut_color: None,
+            runner_mode: RunMode::default(),
+            return_to: Return::default(),
+        }
+    }
+
+    pub fn with_shortcut(mut self, shortcut: char) -> Entry {
+        self.shortcut = shortcut;
+        self
+    }
+
+    pub fn with_icon<S: Into<String>>(mut self, icon: S) -> Entry {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Entry {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_id<S: Into<String>>(mut self, id: S) -> Entry {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn with_value<S: Into<String>>(mut self, value: S) -> Entry {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Runs the command when the entry is picked. Strings are run as shell scripts.
+    pub fn with_command<C: Into<Command>>(mut self, command: C) -> Entry {
+        self.command = command.into();
+        self
+    }
+
+    pub fn with_shortcut_color(mut self, color: Color) -> Entry {
+        self.shortcut_color = Some(color);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: RunMode) -> Entry {
+        self.runner_mode = mode;
+        self
+    }
+
+    pub fn with_return(mut self, return_to: Return) -> Entry {
+        self.return_to = return_to;
+        self
+    }
+
     pub fn shortcut(&self) -> char {
         self.shortcut
     }
@@ -118,6 +228,23 @@ impl Entry {
         &self.title
     }
 
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// What to print when the entry is picked with --print-choice; its value, or else its title.
+    pub fn value(&self) -> &str {
+        self.value.as_deref().unwrap_or(&self.title)
+    }
+
     pub fn shortcut_color(&self) -> Option<Color> {
         self.shortcut_color
     }
@@ -133,6 +260,39 @@ impl Entry {
     pub fn runner_mode(&self) -> RunMode {
         self.runner_mode
     }
+
+    /// The text that `copy` and `type` entries use; the value of the entry if it has one, or else
+    /// the text of its command.
+    pub fn snippet(&self) -> Option<String> {
+        self.value.clone().or_else(|| self.command_line())
+    }
+
+    /// The command of the entry as a line of text, like it would be typed in a shell. Words of
+    /// executables are quoted when needed, so that the shell runs the same command.
+    pub fn command_line(&self) -> Option<String> {
+        match self.command {
+            Command::None => None,
+            Command::ShellScript(ref script) => Some(script.clone()),
+            Command::Executable { ref name, ref args } => {
+                let words: Vec<String> = Some(name)
+                    .into_iter()
+                    .chain(args)
+                    .map(|word| shell_quote(word))
+                    .collect();
+                Some(words.join(" "))
+            }
+        }
+    }
+}
+
+/// Quotes a word for POSIX shells, unless it only has characters that the shell leaves alone.
+fn shell_quote(word: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !word.is_empty() && word.chars().all(is_plain) {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
 }
 
 impl<'a> From<&'a Entry> for Action {
@@ -150,6 +310,13 @@ impl<'a> From<&'a Entry> for Action {
                 command,
                 return_to: entry.return_to.clone(),
             },
+            RunMode::Copy => Action::Copy {
+                text: entry.snippet().unwrap_or_default(),
+                return_to: entry.return_to.clone(),
+            },
+            RunMode::Type => Action::Type {
+                text: entry.snippet().unwrap_or_default(),
+            },
         }
     }
 }
@@ -161,24 +328,21 @@ impl Default for Command {
     }
 }
 
-impl Default for RunMode {
-    fn default() -> RunMode {
-        RunMode::Normal
+impl<'a> From<&'a str> for Command {
+    fn from(script: &'a str) -> Command {
+        Command::ShellScript(script.to_owned())
     }
 }
 
-impl RunMode {
-    fn is_wait(self) -> bool {
-        match self {
-            RunMode::Wait => true,
-            _ => false,
-        }
+impl From<String> for Command {
+    fn from(script: String) -> Command {
+        Command::ShellScript(script)
     }
 }
 
-impl Default for Return {
-    fn default() -> Return {
-        Return::Quit
+impl RunMode {
+    fn is_wait(self) -> bool {
+        matches!(self, RunMode::Wait)
     }
 }
 
@@ -235,6 +399,67 @@ impl<'de> Deserialize<'de> for Return {
     }
 }
 
+impl Serialize for Return {
+    /// Serialize in the same format as is parsed; true for "SamePage", false for "Quit" and a
+    /// string for other pages.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            Return::Quit => serializer.serialize_bool(false),
+            Return::SamePage => serializer.serialize_bool(true),
+            Return::OtherPage(ref page_name) => serializer.serialize_str(page_name),
+        }
+    }
+}
+
+impl JsonSchema for Return {
+    fn schema_name() -> String {
+        String::from("Return")
+    }
+
+    /// Mirrors the ReturnVisitor: a boolean, a page name, or nothing at all.
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(
+                vec![
+                    InstanceType::Boolean,
+                    InstanceType::String,
+                    InstanceType::Null,
+                ]
+                .into(),
+            ),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl fmt::Display for RunMode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            RunMode::Normal => "normal",
+            RunMode::Wait => "wait",
+            RunMode::Exec => "exec",
+            RunMode::Background => "background",
+            RunMode::Copy => "copy",
+            RunMode::Type => "type",
+        };
+        formatter.write_str(name)
+    }
+}
+
+impl fmt::Display for Return {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Return::Quit => write!(formatter, "quit"),
+            Return::SamePage => write!(formatter, "same page"),
+            Return::OtherPage(ref page_name) => write!(formatter, "page {}", page_name),
+        }
+    }
+}
+
 impl fmt::Display for Command {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -289,6 +514,46 @@ mod tests {
         assert_eq!(&format!("{}", none), "(Nothing)");
     }
 
+    #[test]
+    fn it_uses_the_value_or_the_command_as_snippet() {
+        let entry = Entry::new('a', "A");
+        assert_eq!(entry.snippet(), None);
+
+        let entry = entry.with_command(Command::Executable {
+            name: String::from("ls"),
+            args: vec![String::from("-l"), String::from("/")],
+        });
+        assert_eq!(entry.snippet().as_deref(), Some("ls -l /"));
+
+        let entry = entry.with_value("https://example.com");
+        assert_eq!(entry.snippet().as_deref(), Some("https://example.com"));
+
+        let entry = Entry::new('c', "Commit").with_command(Command::Executable {
+            name: String::from("git"),
+            args: vec![
+                String::from("commit"),
+                String::from("-m"),
+                String::from("fix bug"),
+                String::from("it's $HOME"),
+                String::new(),
+            ],
+        });
+        assert_eq!(
+            entry.snippet().as_deref(),
+            Some(r#"git commit -m 'fix bug' 'it'\''s $HOME' ''"#)
+        );
+    }
+
+    #[test]
+    fn it_serializes_returns_like_they_are_written() {
+        assert_eq!(serde_json::to_string(&Return::Quit).unwrap(), "false");
+        assert_eq!(serde_json::to_string(&Return::SamePage).unwrap(), "true");
+        assert_eq!(
+            serde_json::to_string(&Return::OtherPage("packages".into())).unwrap(),
+            "\"packages\""
+        );
+    }
+
     #[test]
     fn it_deserializes_command() {
         assert_eq!(
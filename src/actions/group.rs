@@ -1,14 +1,49 @@
 use super::{Entry, Settings};
+use schemars::JsonSchema;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Group {
     title: Option<String>,
     settings: Option<Settings>,
+    /// Width of the group when shown as a column, instead of the width of its entries.
+    width: Option<u16>,
+    /// Minimum width of the group when shown as a column.
+    min_width: Option<u16>,
     entries: Vec<Entry>,
 }
 
 impl Group {
+    pub fn new() -> Group {
+        Group {
+            title: None,
+            settings: None,
+            width: None,
+            min_width: None,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Group {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_width(mut self, width: u16) -> Group {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn with_min_width(mut self, min_width: u16) -> Group {
+        self.min_width = Some(min_width);
+        self
+    }
+
+    pub fn with_entry(mut self, entry: Entry) -> Group {
+        self.entries.push(entry);
+        self
+    }
+
     pub fn title(&self) -> Option<&str> {
         self.title.as_ref().map(String::as_ref)
     }
@@ -17,7 +52,25 @@ impl Group {
         self.settings.as_ref()
     }
 
+    pub(super) fn settings_mut(&mut self) -> Option<&mut Settings> {
+        self.settings.as_mut()
+    }
+
+    pub fn width(&self) -> Option<u16> {
+        self.width
+    }
+
+    pub fn min_width(&self) -> Option<u16> {
+        self.min_width
+    }
+
     pub fn entries(&self) -> &[Entry] {
         self.entries.as_slice()
     }
 }
+
+impl Default for Group {
+    fn default() -> Group {
+        Group::new()
+    }
+}
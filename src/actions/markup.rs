@@ -0,0 +1,461 @@
+use super::style::{Attribute, Color, ColorSupport, TextStyle};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Modifier, Style};
+use tui::widgets::Widget;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Widget that draws text with markup, like `Paragraph` does.
+///
+/// The markup is compatible with the one in `Paragraph` (`{fg=red;mod=bold Some text}`), but
+/// also understands every color that the action file does, like `{fg=#ff8800 text}` and
+/// `{bg=236 text}`. Marks can also be nested, so that a styled header can contain marks of its
+/// own. A literal brace or backslash is written by escaping it with a backslash.
+pub struct Markup<'a> {
+    text: &'a str,
+    style: TextStyle,
+    raw: bool,
+    wrap: bool,
+    support: ColorSupport,
+}
+
+impl<'a> Markup<'a> {
+    pub fn new(text: &'a str) -> Markup<'a> {
+        Markup {
+            text,
+            style: TextStyle::default(),
+            raw: false,
+            wrap: false,
+            support: ColorSupport::current(),
+        }
+    }
+
+    /// The style of all text that is not inside a mark.
+    pub fn style(mut self, style: TextStyle) -> Markup<'a> {
+        self.style = style;
+        self
+    }
+
+    /// Do not parse markup, and show the text as-is.
+    pub fn raw(mut self, raw: bool) -> Markup<'a> {
+        self.raw = raw;
+        self
+    }
+
+    /// Wrap lines that are too long instead of cutting them off.
+    pub fn wrap(mut self, wrap: bool) -> Markup<'a> {
+        self.wrap = wrap;
+        self
+    }
+
+    fn base_style(&self) -> Style {
+        self.style.apply(Style::default(), self.support)
+    }
+
+    /// Splits the text into graphemes, along with the style each one should be drawn in.
+    fn styled_graphemes(&self) -> Vec<(&'a str, Style)> {
+        let base_style = self.base_style();
+        let graphemes = UnicodeSegmentation::graphemes(self.text, true);
+        if self.raw {
+            return graphemes.map(|grapheme| (grapheme, base_style)).collect();
+        }
+
+        let mut styled = Vec::new();
+        let mut styles = vec![base_style];
+        let mut command: Option<String> = None;
+        let mut escaping = false;
+
+        for grapheme in graphemes {
+            let current_style = *styles.last().unwrap_or(&base_style);
+
+            if let Some(ref mut command_string) = command {
+                match grapheme {
+                    " " => {
+                        styles.push(apply_commands(current_style, command_string, self.support));
+                        command = None;
+                    }
+                    // A mark without any text
+                    "}" => command = None,
+                    _ => command_string.push_str(grapheme),
+                }
+            } else if escaping {
+                escaping = false;
+                if grapheme != "{" && grapheme != "}" && grapheme != "\\" {
+                    styled.push(("\\", current_style));
+                }
+                styled.push((grapheme, current_style));
+            } else {
+                match grapheme {
+                    "\\" => escaping = true,
+                    "{" => command = Some(String::new()),
+                    "}" if styles.len() > 1 => {
+                        styles.pop();
+                    }
+                    _ => styled.push((grapheme, current_style)),
+                }
+            }
+        }
+
+        if escaping {
+            styled.push(("\\", *styles.last().unwrap_or(&base_style)));
+        }
+
+        styled
+    }
+}
+
+/// Applies commands like `fg=red;mod=bold` on top of a style.
+fn apply_commands(style: Style, commands: &str, support: ColorSupport) -> Style {
+    let mut style = style;
+    for command in commands.split(';') {
+        let mut parts = command.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some("fg"), Some(value)) => style.fg = markup_color(value).to_tui(support),
+            (Some("bg"), Some(value)) => style.bg = markup_color(value).to_tui(support),
+            (Some("mod"), Some(value)) => {
+                style.modifier = value
+                    .parse::<Attribute>()
+                    .map(Attribute::to_tui)
+                    .unwrap_or(Modifier::Reset)
+            }
+            _ => {}
+        }
+    }
+    style
+}
+
+/// Parses a color in markup. Besides the colors in action files, the names that `Paragraph`
+/// markup uses are also understood. Unknown colors reset the color, like in `Paragraph`.
+fn markup_color(value: &str) -> Color {
+    match value {
+        "gray" => Color::White,
+        "dark_gray" => Color::BrightBlack,
+        "light_red" => Color::BrightRed,
+        "light_green" => Color::BrightGreen,
+        "light_yellow" => Color::BrightYellow,
+        "light_blue" => Color::BrightBlue,
+        "light_magenta" => Color::BrightMagenta,
+        "light_cyan" => Color::BrightCyan,
+        other => other.parse().unwrap_or_default(),
+    }
+}
+
+/// The text that markup shows on screen, without any of its marks.
+pub fn plain(text: &str) -> String {
+    Markup::new(text)
+        .styled_graphemes()
+        .into_iter()
+        .map(|(grapheme, _)| grapheme)
+        .collect()
+}
+
+/// Escapes text so that it is shown as-is when it is part of markup.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for chr in text.chars() {
+        if chr == '{' || chr == '}' || chr == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(chr);
+    }
+    escaped
+}
+
+impl<'a> Widget for Markup<'a> {
+    fn draw(&mut self, area: &Rect, buf: &mut Buffer) {
+        if area.height < 1 || area.width < 1 {
+            return;
+        }
+
+        let base_style = self.base_style();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_style(base_style);
+            }
+        }
+
+        let (placed, _) = self.layout(area.width as usize);
+        for grapheme in placed {
+            if grapheme.y >= area.height as usize {
+                break;
+            }
+
+            let x = area.left() + grapheme.x as u16;
+            let y = area.top() + grapheme.y as u16;
+            buf.get_mut(x, y)
+                .set_symbol(grapheme.symbol)
+                .set_style(grapheme.style);
+            // The terminal draws wide graphemes over the cells after them, so those cells must
+            // not draw anything themselves.
+            for covered in 1..grapheme.width as u16 {
+                buf.get_mut(x + covered, y)
+                    .set_symbol("")
+                    .set_style(grapheme.style);
+            }
+        }
+    }
+}
+
+/// A grapheme with its position on screen, counted in columns and lines from the top left corner
+/// of the text.
+struct Placed<'a> {
+    x: usize,
+    y: usize,
+    width: usize,
+    symbol: &'a str,
+    style: Style,
+}
+
+/// Pieces of a line that wrapping treats differently.
+enum Token<'a> {
+    Newline,
+    Space(Style),
+    Word(Vec<(&'a str, Style)>),
+}
+
+impl<'a> Markup<'a> {
+    /// Places every grapheme on screen, for a text that can be as wide as the given width. Also
+    /// returns the number of lines that the text needs.
+    ///
+    /// When wrapping, lines are broken between words. Only words that are wider than a whole line
+    /// are broken up themselves. Spaces that do not fit on a line are dropped, so trailing
+    /// whitespace never leaves an empty line behind. Without wrapping, lines are cut off instead.
+    fn layout(&self, width: usize) -> (Vec<Placed<'a>>, usize) {
+        let mut placed = Vec::new();
+        let mut x = 0;
+        let mut y = 0;
+        // Set when a space did not fit, so that the next word starts a new line.
+        let mut wrap_pending = false;
+        // Set when a line is cut off, to skip the rest of it.
+        let mut cut = false;
+
+        let mut place = |x: &mut usize, y: usize, symbol: &'a str, style: Style| {
+            let grapheme_width = symbol.width();
+            if grapheme_width == 0 || *x + grapheme_width > width {
+                return false;
+            }
+            placed.push(Placed {
+                x: *x,
+                y,
+                width: grapheme_width,
+                symbol,
+                style,
+            });
+            *x += grapheme_width;
+            true
+        };
+
+        for token in tokens(self.styled_graphemes()) {
+            match token {
+                Token::Newline => {
+                    x = 0;
+                    y += 1;
+                    wrap_pending = false;
+                    cut = false;
+                }
+                _ if cut => {}
+                Token::Space(style) => {
+                    if !wrap_pending && !place(&mut x, y, " ", style) {
+                        wrap_pending = self.wrap;
+                        cut = !self.wrap;
+                    }
+                }
+                Token::Word(graphemes) => {
+                    if self.wrap {
+                        let word_width: usize =
+                            graphemes.iter().map(|(symbol, _)| symbol.width()).sum();
+                        if wrap_pending || (x > 0 && x + word_width > width) {
+                            x = 0;
+                            y += 1;
+                            wrap_pending = false;
+                        }
+                    }
+
+                    for (symbol, style) in graphemes {
+                        if place(&mut x, y, symbol, style) || symbol.width() == 0 {
+                            continue;
+                        }
+                        if !self.wrap {
+                            cut = true;
+                            break;
+                        }
+                        if x > 0 {
+                            x = 0;
+                            y += 1;
+                            place(&mut x, y, symbol, style);
+                        }
+                    }
+                }
+            }
+        }
+
+        (placed, y + 1)
+    }
+}
+
+/// Groups graphemes into words, spaces and line breaks.
+fn tokens<'a>(graphemes: Vec<(&'a str, Style)>) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    let mut word = Vec::new();
+
+    for (grapheme, style) in graphemes {
+        if grapheme != " " && grapheme != "\n" {
+            word.push((grapheme, style));
+            continue;
+        }
+
+        if !word.is_empty() {
+            tokens.push(Token::Word(std::mem::take(&mut word)));
+        }
+        if grapheme == " " {
+            tokens.push(Token::Space(style));
+        } else {
+            tokens.push(Token::Newline);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(Token::Word(word));
+    }
+
+    tokens
+}
+
+/// The number of lines that text with markup needs when it is wrapped to the given width.
+pub fn required_lines(text: &str, width: usize) -> usize {
+    let (_, lines) = Markup::new(text).wrap(true).layout(width.max(1));
+    lines
+}
+
+/// The width of the widest line of text with markup, in columns on screen.
+pub fn width(text: &str) -> usize {
+    plain(text)
+        .lines()
+        .map(UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tui::style::Color as TuiColor;
+
+    fn styled(text: &str) -> Vec<(String, Style)> {
+        // Merge graphemes with the same style to make the assertions easier to read.
+        let mut result: Vec<(String, Style)> = Vec::new();
+        let markup = Markup {
+            support: ColorSupport::TrueColor,
+            ..Markup::new(text)
+        };
+        for (grapheme, style) in markup.styled_graphemes() {
+            match result.last_mut() {
+                Some((string, last_style)) if *last_style == style => string.push_str(grapheme),
+                _ => result.push((grapheme.to_owned(), style)),
+            }
+        }
+        result
+    }
+
+    fn fg(color: TuiColor) -> Style {
+        Style::default().fg(color)
+    }
+
+    #[test]
+    fn it_parses_paragraph_markup() {
+        assert_eq!(
+            styled("[{fg=red a}] {fg=light_blue;mod=bold Title}"),
+            vec![
+                (String::from("["), Style::default()),
+                (String::from("a"), fg(TuiColor::Red)),
+                (String::from("] "), Style::default()),
+                (
+                    String::from("Title"),
+                    fg(TuiColor::LightBlue).modifier(Modifier::Bold)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_extended_colors_and_nested_marks() {
+        assert_eq!(
+            styled("{fg=#ff8800 Hello {bg=236 there} you}"),
+            vec![
+                (String::from("Hello "), fg(TuiColor::Rgb(255, 136, 0))),
+                (
+                    String::from("there"),
+                    fg(TuiColor::Rgb(255, 136, 0)).bg(TuiColor::Rgb(48, 48, 48))
+                ),
+                (String::from(" you"), fg(TuiColor::Rgb(255, 136, 0))),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_escapes_markup() {
+        let text = format!("{{fg=red {}}} }}", escape("{\\}"));
+        assert_eq!(
+            styled(&text),
+            vec![
+                (String::from("{\\}"), fg(TuiColor::Red)),
+                (String::from(" }"), Style::default()),
+            ]
+        );
+    }
+
+    fn draw(markup: Markup, width: u16, height: u16) -> (Vec<String>, Buffer) {
+        let area = Rect::new(0, 0, width, height);
+        let mut buffer = Buffer::empty(area);
+        markup.wrap(true).draw(&area, &mut buffer);
+
+        let lines = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol.clone())
+                    .collect()
+            })
+            .collect();
+        (lines, buffer)
+    }
+
+    #[test]
+    fn it_draws_and_wraps_text_between_words() {
+        let (lines, buffer) = draw(Markup::new("ab {mod=bold cdef} gh\nij"), 5, 4);
+        assert_eq!(lines, vec!["ab   ", "cdef ", "gh   ", "ij   "]);
+        assert_eq!(buffer.get(0, 1).style.modifier, Modifier::Bold);
+        assert_eq!(buffer.get(3, 1).style.modifier, Modifier::Bold);
+        assert_eq!(buffer.get(0, 2).style.modifier, Modifier::Reset);
+
+        let (lines, _) = draw(Markup::new("abcdefgh ij"), 5, 3);
+        assert_eq!(lines, vec!["abcde", "fgh  ", "ij   "]);
+    }
+
+    #[test]
+    fn it_draws_mixed_width_text() {
+        // Wide graphemes cover the cell after them, which is left empty.
+        let (lines, _) = draw(Markup::new("漢字 ok {fg=red café} 👍!"), 6, 4);
+        assert_eq!(lines, vec!["漢字  ", "ok    ", "café  ", "👍!   "]);
+
+        let (lines, _) = draw(Markup::new("a漢字b"), 4, 3);
+        assert_eq!(lines, vec!["a漢 ", "字b ", "    "]);
+    }
+
+    #[test]
+    fn it_measures_text_on_screen() {
+        assert_eq!(width("{fg=red é漢} x"), 5);
+        assert_eq!(width("ab\n{mod=bold 日本語}"), 6);
+        assert_eq!(required_lines("{fg=red 漢字漢字} ab", 4), 3);
+        assert_eq!(required_lines("[a] Title  ", 9), 1);
+        assert_eq!(required_lines("one\n\nthree", 10), 3);
+    }
+
+    #[test]
+    fn it_removes_markup_from_plain_text() {
+        assert_eq!(
+            plain("[{fg=red a}] {mod=bold Title \\{x\\}}"),
+            "[a] Title {x}"
+        );
+        assert_eq!(plain("no markup"), "no markup");
+    }
+}
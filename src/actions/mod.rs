@@ -1,15 +1,21 @@
 mod action_file;
 mod entry;
 mod group;
+mod markup;
 mod page;
 mod rendering;
 mod settings;
+mod style;
+mod theme;
 mod validator;
 
 pub use self::action_file::ActionFile;
-pub use self::entry::{Action, Command, Entry, RunMode, Return};
+pub(crate) use self::entry::Action;
+pub use self::entry::{Command, Entry, Return, RunMode};
 pub use self::group::Group;
 pub use self::page::Page;
-pub use self::rendering::render;
-pub use self::settings::{Color, Layout, Settings, SettingsAccumulator};
+pub(crate) use self::rendering::{render, render_help, HELP_KEY, REPEAT_KEY};
+pub use self::settings::{Layout, Position, Settings, SettingsAccumulator};
+pub(crate) use self::style::{palette_index, ColorSupport};
+pub use self::style::{Color, TextStyle};
 pub use self::validator::ValidationError;
@@ -1,6 +1,7 @@
 use super::{Entry, Group, Settings};
+use schemars::JsonSchema;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
     #[serde(default = "Page::default_title")]
@@ -12,10 +13,35 @@ pub struct Page {
 }
 
 impl Page {
+    pub fn new<S: Into<String>>(title: S) -> Page {
+        Page {
+            title: title.into(),
+            header: None,
+            footer: None,
+            settings: None,
+            groups: Vec::new(),
+        }
+    }
+
     fn default_title() -> String {
         String::from("Tydra")
     }
 
+    pub fn with_header<S: Into<String>>(mut self, header: S) -> Page {
+        self.header = Some(header.into());
+        self
+    }
+
+    pub fn with_footer<S: Into<String>>(mut self, footer: S) -> Page {
+        self.footer = Some(footer.into());
+        self
+    }
+
+    pub fn with_group(mut self, group: Group) -> Page {
+        self.groups.push(group);
+        self
+    }
+
     pub fn all_entries(&self) -> impl Iterator<Item = &Entry> {
         self.groups.iter().flat_map(|group| group.entries())
     }
@@ -25,6 +51,10 @@ impl Page {
             .find(|entry| entry.shortcut() == shortcut)
     }
 
+    pub fn entry_with_id(&self, id: &str) -> Option<&Entry> {
+        self.all_entries().find(|entry| entry.id() == Some(id))
+    }
+
     pub fn title(&self) -> &str {
         &self.title
     }
@@ -41,6 +71,14 @@ impl Page {
         self.settings.as_ref()
     }
 
+    pub(super) fn settings_mut(&mut self) -> Option<&mut Settings> {
+        self.settings.as_mut()
+    }
+
+    pub(super) fn groups_mut(&mut self) -> &mut [Group] {
+        self.groups.as_mut_slice()
+    }
+
     pub fn groups(&self) -> &[Group] {
         self.groups.as_slice()
     }
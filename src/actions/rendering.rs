@@ -1,141 +1,754 @@
-use crate::actions::{Entry, Group, Layout, Page, SettingsAccumulator};
+use super::markup::{self, required_lines, Markup};
+use super::style::ColorSupport;
+use crate::actions::{
+    Color, Command, Entry, Group, Layout, Page, Position, SettingsAccumulator, TextStyle,
+};
+use crate::status::Status;
 use failure::Error;
+use std::ops::Range;
+use tui::backend::Backend;
 use tui::layout::{self, Direction, Rect, Size};
-use tui::widgets::{Paragraph, Widget};
-use crate::Term;
+use tui::widgets::{Block, Borders, Widget};
+use tui::Terminal;
 
-pub fn render(term: &mut Term, page: &Page, settings: &SettingsAccumulator) -> Result<(), Error> {
-    match settings.layout() {
-        Layout::List => render_list_layout(term, page, settings),
-        Layout::Columns => render_columns_layout(term, page, settings),
+/// Key that opens the help overlay, unless an entry on the page uses it as its shortcut.
+pub const HELP_KEY: char = '?';
+
+/// Key that runs the last entry again, unless an entry on the page uses it as its shortcut.
+pub const REPEAT_KEY: char = '.';
+
+/// Keys that work on every page, besides the help key.
+const BUILTIN_KEYS: [(&str, &str); 3] = [
+    ("Esc", "Exit tydra"),
+    ("Ctrl-L", "Redraw the menu"),
+    ("Ctrl-Z", "Suspend tydra"),
+];
+
+/// Renders the page, and the status bar or a status message on the last line of the screen. The
+/// entry with the selected shortcut is highlighted.
+pub fn render<B: Backend>(
+    term: &mut Terminal<B>,
+    page: &Page,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+    status: &Status,
+) -> Result<(), Error> {
+    let area = render_status_area(term, page, settings, status)?;
+
+    let border = if settings.boxed { 2 } else { 0 };
+    let available_width = area.width.saturating_sub(border) as usize;
+    let available_height = area.height.saturating_sub(border) as usize;
+    let layout = effective_layout(page, settings, available_width, available_height);
+
+    let area = menu_area(area, page, settings, layout);
+    let area = if settings.boxed {
+        render_page_box(term, area, page, settings)
+    } else {
+        area
+    };
+
+    match layout {
+        Layout::List if settings.boxed => {
+            render_boxed_list_layout(term, area, page, settings, selected)
+        }
+        Layout::List => render_list_layout(term, area, page, settings, selected),
+        Layout::Columns => render_columns_layout(term, area, page, settings, selected),
+        Layout::Grid | Layout::Auto => render_grid_layout(term, area, page, settings, selected),
     }
+
+    term.draw().map_err(|e| e.into())
 }
 
-pub fn render_list_layout(
-    term: &mut Term,
+/// Renders the help overlay for the page, which explains every entry on it and the keys that work
+/// everywhere.
+pub fn render_help<B: Backend>(
+    term: &mut Terminal<B>,
     page: &Page,
     settings: &SettingsAccumulator,
+    status: &Status,
 ) -> Result<(), Error> {
-    let size = term.size()?;
-    let max_width = size.width as usize;
+    let area = render_status_area(term, page, settings, status)?;
+
+    let title = format!("Help: {}", markup::plain(page.title()));
+    let inner = render_box(term, area, &title, settings.theme.title, settings);
 
+    let text = help_text(page, settings, status.repeat.as_deref());
+    Markup::new(&text).wrap(true).render(term, &inner);
+
+    term.draw().map_err(|e| e.into())
+}
+
+/// Renders the status bar on the last line of the screen when it is enabled, or else the status
+/// message if there is one. Returns the rest of the screen.
+fn render_status_area<B: Backend>(
+    term: &mut Terminal<B>,
+    page: &Page,
+    settings: &SettingsAccumulator,
+    status: &Status,
+) -> Result<Rect, Error> {
+    let mut area = term.size()?;
+    if !settings.status_bar && status.message.is_none() {
+        return Ok(area);
+    }
+
+    area.height = area.height.saturating_sub(1);
+    let status_area = Rect {
+        y: area.y + area.height,
+        height: 1,
+        ..area
+    };
+    if settings.status_bar {
+        let text = status_bar_text(page, settings, status, status_area.width as usize);
+        Markup::new(&text)
+            .style(settings.theme.status_bar)
+            .render(term, &status_area);
+    } else if let Some(ref message) = status.message {
+        render_status(term, status_area, &message.text, message.is_error);
+    }
+
+    Ok(area)
+}
+
+fn render_status<B: Backend>(term: &mut Terminal<B>, rect: Rect, message: &str, is_error: bool) {
+    // Messages can contain anything, like snippets of the action file, so markup must not be
+    // parsed in them.
+    let style = if is_error {
+        TextStyle::fg(Color::Red)
+    } else {
+        TextStyle::default()
+    };
+    Markup::new(message)
+        .raw(true)
+        .wrap(true)
+        .style(style)
+        .render(term, &rect);
+}
+
+/// The status bar shows the path of pages to the current one, how the last command went and the
+/// status message on the left, and hints for keys on the right when there is room for them.
+fn status_bar_text(
+    page: &Page,
+    settings: &SettingsAccumulator,
+    status: &Status,
+    width: usize,
+) -> String {
+    let theme = &settings.theme;
+    let mut parts = vec![markup::escape(&status.page_path.join(" › "))];
+
+    if let Some(ref result) = status.last_run {
+        let (mark, color) = if result.success() {
+            ("✓", Color::Green)
+        } else {
+            ("✗", Color::Red)
+        };
+        let text = format!("{} {}", mark, result);
+        parts.push(TextStyle::fg(color).markup(&markup::escape(&text)));
+    }
+
+    if let Some(ref message) = status.message {
+        // Only the first line fits in the status bar.
+        let text = markup::escape(message.text.lines().next().unwrap_or(""));
+        if message.is_error {
+            parts.push(TextStyle::fg(Color::Red).markup(&text));
+        } else {
+            parts.push(text);
+        }
+    }
+
+    let separator = theme.separator.markup(" │ ");
+    let mut text = format!(" {}", parts.join(&separator));
+
+    let mut hints = Vec::new();
+    if let (Some(title), None) = (&status.repeat, page.entry_with_shortcut(REPEAT_KEY)) {
+        let key = theme
+            .shortcut
+            .markup(&markup::escape(&REPEAT_KEY.to_string()));
+        hints.push(format!("{} repeat: {}", key, title));
+    }
+    if page.entry_with_shortcut(HELP_KEY).is_none() {
+        hints.push(format!(
+            "{} help",
+            theme.shortcut.markup(&markup::escape("?"))
+        ));
+    }
+    hints.push(format!("{} quit", theme.shortcut.markup("Esc")));
+    let hints = format!("{} ", hints.join("  "));
+
+    let used_width = markup::width(&text) + markup::width(&hints);
+    if used_width < width {
+        text.push_str(&" ".repeat(width - used_width));
+        text.push_str(&hints);
+    }
+
+    text
+}
+
+/// Decides on the layout to render the page in. Layouts that do not fit in the width fall back
+/// to the list layout.
+///
+/// The auto layout picks the densest layout that fits on the screen; columns, then the grid, then
+/// the list. When none of them fit, it picks the one that needs the fewest lines.
+fn effective_layout(
+    page: &Page,
+    settings: &SettingsAccumulator,
+    width: usize,
+    height: usize,
+) -> Layout {
+    let column_widths: Vec<usize> = page
+        .groups()
+        .iter()
+        .map(|group| column_width(group, settings))
+        .collect();
+    let columns_fit = column_widths.iter().sum::<usize>() <= width;
+    let all_columns_fit = column_widths
+        .iter()
+        .all(|column_width| *column_width <= width);
+
+    match settings.layout() {
+        Layout::List => Layout::List,
+        Layout::Columns if columns_fit => Layout::Columns,
+        Layout::Grid if all_columns_fit => Layout::Grid,
+        Layout::Auto => {
+            let candidates = [
+                (Layout::Columns, columns_fit),
+                (Layout::Grid, all_columns_fit),
+                (Layout::List, true),
+            ];
+            let heights: Vec<(Layout, usize)> = candidates
+                .iter()
+                .filter(|(_, fits_width)| *fits_width)
+                .map(|(layout, _)| (*layout, content_size(page, settings, *layout, width).1))
+                .collect();
+            heights
+                .iter()
+                .find(|(_, layout_height)| *layout_height <= height)
+                .or_else(|| {
+                    heights
+                        .iter()
+                        .min_by_key(|(_, layout_height)| *layout_height)
+                })
+                .map_or(Layout::List, |(layout, _)| *layout)
+        }
+        _ => Layout::List,
+    }
+}
+
+/// The area of the screen that the menu is rendered in.
+///
+/// A menu at the top of the screen without a box gets the whole screen. Otherwise the menu is as
+/// high as it needs to be, and also only as wide as it needs to be if it is centered.
+fn menu_area(screen: Rect, page: &Page, settings: &SettingsAccumulator, layout: Layout) -> Rect {
+    if settings.position == Position::Top && !settings.boxed {
+        return screen;
+    }
+
+    let border = if settings.boxed { 2 } else { 0 };
+    let available_width = screen.width.saturating_sub(border) as usize;
+    let (content_width, content_height) = content_size(page, settings, layout, available_width);
+
+    let width = match settings.position {
+        Position::Center => (content_width as u16 + border).min(screen.width),
+        _ => screen.width,
+    };
+    let height = (content_height as u16 + border).min(screen.height);
+
+    let y = match settings.position {
+        Position::Top => screen.y,
+        Position::Bottom => screen.bottom() - height,
+        Position::Center => screen.y + (screen.height - height) / 2,
+    };
+
+    Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y,
+        width,
+        height,
+    }
+}
+
+/// The width and height that the page needs in the given layout, when it can be at most as wide
+/// as the given width. This does not include the box around the page.
+fn content_size(
+    page: &Page,
+    settings: &SettingsAccumulator,
+    layout: Layout,
+    max_width: usize,
+) -> (usize, usize) {
+    let boxed = settings.boxed;
+    let max_width = max_width.max(1);
+
+    // The title is shown in the border of the box, so the box must be wide enough for it.
+    let title_width = if boxed {
+        markup::width(page.title()) + 2
+    } else {
+        markup::width(page.title()) + 6
+    };
+    let text_width = title_width
+        .max(page.header().map_or(0, markup::width))
+        .max(page.footer().map_or(0, markup::width));
+
+    let (width, content_height) = match layout {
+        Layout::List => {
+            let groups_width = page
+                .groups()
+                .iter()
+                .map(|group| list_group_width(group, settings))
+                .max()
+                .unwrap_or(0);
+            let width = text_width.max(groups_width).min(max_width);
+
+            let height = if boxed {
+                page.groups()
+                    .iter()
+                    .map(|group| list_group_box_height(group, settings, width))
+                    .sum()
+            } else {
+                let text = list_text(page, settings, None, width);
+                required_lines(&text, width)
+            };
+            (width, height)
+        }
+        Layout::Columns | Layout::Grid | Layout::Auto => {
+            let groups = page.groups();
+            let column_widths: Vec<usize> = groups
+                .iter()
+                .map(|group| column_width(group, settings))
+                .collect();
+            let rows = match layout {
+                Layout::Columns => std::iter::once(0..groups.len()).collect(),
+                _ => grid_rows(&column_widths, max_width),
+            };
+            let grid_width = rows
+                .iter()
+                .map(|row| column_widths[row.clone()].iter().sum())
+                .max()
+                .unwrap_or(0);
+            let width = text_width.max(grid_width).min(max_width);
+
+            let title_lines = if boxed { 0 } else { 1 };
+            let rows_height: usize = grid_row_heights(groups, settings, &column_widths, &rows)
+                .iter()
+                .sum();
+            (width, title_lines + rows_height)
+        }
+    };
+
+    // The unboxed list layout already includes the header and footer in its text.
+    let height = if layout == Layout::List && !boxed {
+        content_height
+    } else {
+        required_lines_option(page.header(), width)
+            + content_height
+            + required_lines_option(page.footer(), width)
+    };
+
+    (width, height)
+}
+
+/// Draws the box around the page, with the title in its border, and returns the area inside it.
+fn render_page_box<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
+    page: &Page,
+    settings: &SettingsAccumulator,
+) -> Rect {
+    let title = markup::plain(page.title());
+    render_box(term, area, &title, settings.theme.title, settings)
+}
+
+/// Draws a box with the title in its top border, and returns the area inside it.
+fn render_box<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
+    title: &str,
+    title_style: TextStyle,
+    settings: &SettingsAccumulator,
+) -> Rect {
+    let support = ColorSupport::current();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(settings.theme.border.apply(Default::default(), support));
+    block.render(term, &area);
+
+    // Block puts every grapheme of its title in a cell of its own, which shifts the border after
+    // wide graphemes, so the title is drawn like any other text. Like the border, it is left out
+    // when the box is too small.
+    let title_width = markup::width(title).min(area.width.saturating_sub(2) as usize);
+    if title_width > 0 && area.height >= 2 {
+        let title_area = Rect::new(area.x + 1, area.y, title_width as u16, 1);
+        Markup::new(title)
+            .raw(true)
+            .style(title_style)
+            .render(term, &title_area);
+    }
+
+    block.inner(&area)
+}
+
+pub fn render_list_layout<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
+    page: &Page,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+) {
+    let text = list_text(page, settings, selected, area.width as usize);
+    Markup::new(&text).wrap(true).render(term, &area);
+}
+
+fn list_text(
+    page: &Page,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+    max_width: usize,
+) -> String {
     let mut text = String::new();
 
-    text.push_str("== ");
-    text.push_str(page.title());
-    text.push_str(" ==");
+    let theme = &settings.theme;
+    text.push_str(&theme.separator.markup("=="));
+    text.push(' ');
+    text.push_str(&theme.title.markup(page.title()));
+    text.push(' ');
+    text.push_str(&theme.separator.markup("=="));
 
-    if let Some(ref header) = page.header() {
-        text.push_str("\n");
-        text.push_str(header);
+    if let Some(header) = page.header() {
+        text.push('\n');
+        text.push_str(&theme.header.markup(header));
     }
 
     for group in page.groups() {
         let settings = settings.with_group(group);
 
         if let Some(title) = group.title() {
-            text.push_str(&format!("\n\n{}:\n", title));
+            let title = format!("{}:", title);
+            text.push_str("\n\n");
+            text.push_str(&settings.theme.group_title.markup(&title));
+            text.push('\n');
         } else {
             text.push_str("\n\n");
         }
 
-        let mut current_line_length = 0;
-        for entry in group.entries() {
-            let entry_length = render_entry(entry).len();
-            if current_line_length + entry_length > max_width {
+        text.push_str(&list_entries_text(group, &settings, selected, max_width));
+    }
+
+    if let Some(footer) = page.footer() {
+        text.push('\n');
+        text.push_str(&theme.footer.markup(footer));
+    }
+
+    text
+}
+
+/// Entries of a group after each other, starting new lines where they would not fit.
+fn list_entries_text(
+    group: &Group,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+    max_width: usize,
+) -> String {
+    let mut text = String::new();
+    let mut current_line_length = 0;
+    let separator = render_separator(settings);
+    let separator_length = markup::width(&separator);
+
+    for entry in group.entries() {
+        let entry_length = entry_width(entry, settings);
+        if current_line_length > 0 {
+            if current_line_length + separator_length + entry_length > max_width {
                 text.push('\n');
                 current_line_length = 0;
+            } else {
+                text.push_str(&separator);
+                current_line_length += separator_length;
             }
-            text.push_str(&render_entry_color(entry, &settings));
-            current_line_length += entry_length;
         }
+        text.push_str(&render_entry(entry, settings, selected));
+        current_line_length += entry_length;
     }
 
-    if let Some(footer) = page.footer() {
-        text.push_str("\n");
-        text.push_str(footer);
-    }
+    text
+}
 
-    Paragraph::default()
-        .wrap(true)
-        .text(&text)
-        .render(term, &size);
-    term.draw().map_err(|e| e.into())
+/// The list layout with a box around each group, and the group title in its border.
+fn render_boxed_list_layout<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
+    page: &Page,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+) {
+    let width = area.width as usize;
+    let groups = page.groups();
+    let header_lines = required_lines_option(page.header(), width);
+    let footer_lines = required_lines_option(page.footer(), width);
+
+    let mut sizes = vec![Size::Fixed(header_lines as u16)];
+    sizes.extend(
+        groups
+            .iter()
+            .map(|group| Size::Fixed(list_group_box_height(group, settings, width) as u16)),
+    );
+    sizes.push(Size::Fixed(footer_lines as u16));
+    sizes.push(Size::Min(0));
+
+    layout::Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&sizes)
+        .render(term, &area, |t, chunks| {
+            let theme = &settings.theme;
+            if let Some(text) = page.header() {
+                render_columns_text(t, chunks[0], text, theme.header);
+            }
+            for (group, chunk) in groups.iter().zip(&chunks[1..]) {
+                let settings = settings.with_group(group);
+                let title = markup::plain(group.title().unwrap_or(""));
+                let inner = render_box(t, *chunk, &title, settings.theme.group_title, &settings);
+                let text = list_entries_text(group, &settings, selected, inner.width as usize);
+                Markup::new(&text).wrap(true).render(t, &inner);
+            }
+            if let Some(text) = page.footer() {
+                render_columns_text(t, chunks[groups.len() + 1], text, theme.footer);
+            }
+        });
 }
 
-pub fn render_columns_layout(
-    term: &mut Term,
+/// The width a group needs in the list layout to show all of its entries on one line.
+fn list_group_width(group: &Group, settings: &SettingsAccumulator) -> usize {
+    let border = if settings.boxed { 2 } else { 0 };
+    let settings = settings.with_group(group);
+    let entries = group.entries();
+    let separators_width =
+        markup::width(&render_separator(&settings)) * entries.len().saturating_sub(1);
+    let entries_width: usize = entries
+        .iter()
+        .map(|entry| entry_width(entry, &settings))
+        .sum();
+    let title_width = group.title().map_or(0, |title| markup::width(title) + 1);
+    (entries_width + separators_width).max(title_width) + border
+}
+
+fn list_group_box_height(group: &Group, settings: &SettingsAccumulator, width: usize) -> usize {
+    let inner_width = width.saturating_sub(2).max(1);
+    let text = list_entries_text(group, settings, None, inner_width);
+    required_lines(&text, inner_width) + 2
+}
+
+pub fn render_columns_layout<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
     page: &Page,
     settings: &SettingsAccumulator,
-) -> Result<(), Error> {
-    let term_size = term.size()?;
-    let width = term_size.width as usize;
+    selected: Option<char>,
+) {
+    let width = area.width as usize;
     let column_widths: Vec<usize> = page
         .groups()
         .iter()
-        .map(|group| {
-            group
-                .entries()
-                .iter()
-                .map(render_entry)
-                .map(|s| s.len())
-                .max()
-                .unwrap_or(0)
-        })
+        .map(|group| column_width(group, settings))
         .collect();
     let required_width = column_widths.iter().sum();
 
-    if width < required_width {
-        render_list_layout(term, page, settings)
-    } else {
-        let header_lines = required_lines_option(page.header(), width);
-        let footer_lines = required_lines_option(page.footer(), width);
-
-        layout::Group::default()
-            .direction(Direction::Vertical)
-            .sizes(&[
-                Size::Fixed(1),
-                Size::Fixed(header_lines as u16),
-                Size::Min(10),
-                Size::Fixed(footer_lines as u16),
-            ])
-            .render(term, &term_size, |t, chunks| {
-                render_columns_title(t, chunks[0], page.title(), required_width);
-                if let Some(text) = page.header() {
-                    render_columns_text(t, chunks[1], &text);
-                }
-                render_columns(t, chunks[2], &column_widths, page.groups(), &settings);
-                if let Some(text) = page.footer() {
-                    render_columns_text(t, chunks[3], &text);
-                }
-            });
+    let title_lines = if settings.boxed { 0 } else { 1 };
+    let header_lines = required_lines_option(page.header(), width);
+    let footer_lines = required_lines_option(page.footer(), width);
+
+    layout::Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&[
+            Size::Fixed(title_lines),
+            Size::Fixed(header_lines as u16),
+            Size::Min(0),
+            Size::Fixed(footer_lines as u16),
+        ])
+        .render(term, &area, |t, chunks| {
+            let theme = &settings.theme;
+            if !settings.boxed {
+                render_columns_title(t, chunks[0], page.title(), required_width, theme.title);
+            }
+            if let Some(text) = page.header() {
+                render_columns_text(t, chunks[1], text, theme.header);
+            }
+            let groups = page.groups();
+            render_columns(t, chunks[2], &column_widths, groups, settings, selected);
+            if let Some(text) = page.footer() {
+                render_columns_text(t, chunks[3], text, theme.footer);
+            }
+        });
+}
+
+/// Renders the groups as columns, like the columns layout, but wraps them into several rows when
+/// they do not fit next to each other.
+pub fn render_grid_layout<B: Backend>(
+    term: &mut Terminal<B>,
+    area: Rect,
+    page: &Page,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+) {
+    let width = area.width as usize;
+    let boxed = settings.boxed;
+    let groups = page.groups();
+    let column_widths: Vec<usize> = groups
+        .iter()
+        .map(|group| column_width(group, settings))
+        .collect();
 
-        term.draw().map_err(|e| e.into())
+    let rows = grid_rows(&column_widths, width);
+    let row_heights = grid_row_heights(groups, settings, &column_widths, &rows);
+    let grid_width = rows
+        .iter()
+        .map(|row| column_widths[row.clone()].iter().sum())
+        .max()
+        .unwrap_or(0);
+    let title_lines = if boxed { 0 } else { 1 };
+    let header_lines = required_lines_option(page.header(), width);
+    let footer_lines = required_lines_option(page.footer(), width);
+
+    let mut sizes = vec![Size::Fixed(title_lines), Size::Fixed(header_lines as u16)];
+    sizes.extend(row_heights.iter().map(|height| Size::Fixed(*height as u16)));
+    sizes.push(Size::Min(0));
+    sizes.push(Size::Fixed(footer_lines as u16));
+
+    layout::Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&sizes)
+        .render(term, &area, |t, chunks| {
+            let theme = &settings.theme;
+            if !boxed {
+                render_columns_title(t, chunks[0], page.title(), grid_width, theme.title);
+            }
+            if let Some(text) = page.header() {
+                render_columns_text(t, chunks[1], text, theme.header);
+            }
+            for (row, chunk) in rows.iter().zip(&chunks[2..]) {
+                let widths = &column_widths[row.clone()];
+                render_columns(t, *chunk, widths, &groups[row.clone()], settings, selected);
+            }
+            if let Some(text) = page.footer() {
+                render_columns_text(t, chunks[chunks.len() - 1], text, theme.footer);
+            }
+        });
+}
+
+/// Splits columns into rows that fit within the width. Columns are kept in order, and each row
+/// gets as many columns as fit in it.
+fn grid_rows(column_widths: &[usize], width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+
+    for (index, column_width) in column_widths.iter().enumerate() {
+        if index > start && row_width + column_width > width {
+            rows.push(start..index);
+            start = index;
+            row_width = 0;
+        }
+        row_width += column_width;
+    }
+    if start < column_widths.len() {
+        rows.push(start..column_widths.len());
     }
+
+    rows
 }
 
-fn render_columns_text(term: &mut Term, rect: Rect, text: &str) {
-    Paragraph::default().text(&text).render(term, &rect);
+/// Lines needed for each row of the grid. Rows are separated by a blank line, unless the groups
+/// are in boxes.
+fn grid_row_heights(
+    groups: &[Group],
+    settings: &SettingsAccumulator,
+    column_widths: &[usize],
+    rows: &[Range<usize>],
+) -> Vec<usize> {
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let height = row
+                .clone()
+                .map(|column| column_height(&groups[column], settings, column_widths[column]))
+                .max()
+                .unwrap_or(0);
+            if index + 1 < rows.len() && !settings.boxed {
+                height + 1
+            } else {
+                height
+            }
+        })
+        .collect()
 }
 
-fn render_columns_title(term: &mut Term, rect: Rect, title: &str, width: usize) {
-    let centered_title = format!("{title:^width$}", title = title, width = width);
-    Paragraph::default()
-        .text(&centered_title)
+/// The width of a group when shown as a column. A `width` set on the group replaces the width
+/// needed by its entries, while `min_width` can only make it wider. The entry separator is the
+/// gap to the next column, and boxes add their borders on top of that.
+fn column_width(group: &Group, settings: &SettingsAccumulator) -> usize {
+    let border = if settings.boxed { 2 } else { 0 };
+    if let Some(width) = group.width() {
+        return width as usize + border;
+    }
+
+    let settings = settings.with_group(group);
+    let entries_width = group
+        .entries()
+        .iter()
+        .map(|entry| entry_width(entry, &settings))
+        .max()
+        .unwrap_or(0);
+    let gap = markup::width(&render_separator(&settings));
+    (entries_width + gap).max(group.min_width().unwrap_or(0) as usize) + border
+}
+
+fn column_height(group: &Group, settings: &SettingsAccumulator, width: usize) -> usize {
+    let (inner_width, extra_lines) = if settings.boxed {
+        (width.saturating_sub(2), 2)
+    } else if group.title().is_some() {
+        (width, 1)
+    } else {
+        (width, 0)
+    };
+    let settings = settings.with_group(group);
+    let entry_lines: usize = group
+        .entries()
+        .iter()
+        .map(|entry| required_lines(&render_entry(entry, &settings, None), inner_width.max(1)))
+        .sum();
+    extra_lines + entry_lines
+}
+
+fn render_columns_text<B: Backend>(
+    term: &mut Terminal<B>,
+    rect: Rect,
+    text: &str,
+    style: TextStyle,
+) {
+    Markup::new(&style.markup(text))
+        .wrap(true)
         .render(term, &rect);
 }
 
-fn render_columns(
-    term: &mut Term,
+fn render_columns_title<B: Backend>(
+    term: &mut Terminal<B>,
+    rect: Rect,
+    title: &str,
+    width: usize,
+    style: TextStyle,
+) {
+    let padding = width.saturating_sub(markup::width(title)) / 2;
+    let centered_title = format!("{}{}", " ".repeat(padding), title);
+    Markup::new(&style.markup(&centered_title)).render(term, &rect);
+}
+
+fn render_columns<B: Backend>(
+    term: &mut Terminal<B>,
     rect: Rect,
     column_widths: &[usize],
     groups: &[Group],
     settings: &SettingsAccumulator,
+    selected: Option<char>,
 ) {
     assert!(column_widths.len() == groups.len());
 
     let sizes: Vec<Size> = column_widths
-        .into_iter()
+        .iter()
         .map(|width| Size::Fixed(*width as u16))
         .collect();
 
@@ -143,43 +756,181 @@ fn render_columns(
         .direction(Direction::Horizontal)
         .sizes(&sizes)
         .render(term, &rect, |t, chunks| {
-            for (chunk, group) in chunks.into_iter().zip(groups.iter()) {
-                render_column(t, *chunk, group, &settings);
+            for (chunk, group) in chunks.iter().zip(groups.iter()) {
+                render_column(t, *chunk, group, settings, selected);
             }
         });
 }
 
-fn render_column(term: &mut Term, rect: Rect, group: &Group, settings: &SettingsAccumulator) {
+fn render_column<B: Backend>(
+    term: &mut Terminal<B>,
+    rect: Rect,
+    group: &Group,
+    settings: &SettingsAccumulator,
+    selected: Option<char>,
+) {
+    // Boxes are set for the whole page, as the widths of all columns depend on it.
+    let boxed = settings.boxed;
     let settings = settings.with_group(group);
     let mut text = String::new();
+    let mut rect = rect;
 
-    if let Some(title) = group.title() {
-        text.push_str(&format!("{}:\n", title));
+    if boxed {
+        let title = markup::plain(group.title().unwrap_or(""));
+        rect = render_box(term, rect, &title, settings.theme.group_title, &settings);
+    } else if let Some(title) = group.title() {
+        let title = format!("{}:", title);
+        text.push_str(&settings.theme.group_title.markup(&title));
+        text.push('\n');
     }
 
     for entry in group.entries() {
-        text.push_str(&render_entry_color(entry, &settings));
+        text.push_str(&render_entry(entry, &settings, selected));
         text.push('\n');
     }
 
-    Paragraph::default()
-        .wrap(true)
-        .text(&text)
-        .render(term, &rect);
+    Markup::new(&text).wrap(true).render(term, &rect);
 }
 
-fn render_entry(entry: &Entry) -> String {
-    format!("[{}] {}  ", entry.shortcut(), entry.title())
+/// Text of the help overlay. Entries are listed like in `--list`, with their description, command,
+/// mode and return target.
+fn help_text(page: &Page, settings: &SettingsAccumulator, repeat: Option<&str>) -> String {
+    let mut text = String::new();
+
+    for group in page.groups() {
+        let settings = settings.with_group(group);
+        if let Some(title) = group.title() {
+            let title = format!("{}:", title);
+            text.push_str(&settings.theme.group_title.markup(&title));
+            text.push('\n');
+        }
+
+        for entry in group.entries() {
+            text.push_str(&render_entry(entry, &settings, None));
+            text.push_str(&markup::escape(&format!(
+                "  ({}, return: {})",
+                entry.runner_mode(),
+                entry.return_to()
+            )));
+            text.push('\n');
+            if let Some(description) = entry.description() {
+                for line in description.lines() {
+                    text.push_str(&format!("    {}\n", markup::escape(line)));
+                }
+            }
+            if *entry.command() != Command::None {
+                for line in entry.command().to_string().lines() {
+                    text.push_str(&format!("    $ {}\n", markup::escape(line)));
+                }
+            }
+        }
+        text.push('\n');
+    }
+
+    let theme = &settings.theme;
+    text.push_str(&theme.group_title.markup("Keys:"));
+    text.push('\n');
+    let help_key = HELP_KEY.to_string();
+    let help = (help_key.as_str(), String::from("Show this help"));
+    let repeat_key = REPEAT_KEY.to_string();
+    let repeat = repeat
+        .filter(|_| page.entry_with_shortcut(REPEAT_KEY).is_none())
+        .map(|title| (repeat_key.as_str(), format!("Run {} again", title)));
+    let keys = Some(help)
+        .filter(|_| page.entry_with_shortcut(HELP_KEY).is_none())
+        .into_iter()
+        .chain(repeat)
+        .chain(
+            BUILTIN_KEYS
+                .iter()
+                .map(|&(key, explanation)| (key, explanation.to_owned())),
+        );
+    for (key, explanation) in keys {
+        text.push_str(&format!(
+            "{open}{key}{close} {explanation}\n",
+            open = theme.brackets.markup("["),
+            key = theme.shortcut.markup(&markup::escape(key)),
+            close = theme.brackets.markup("]"),
+            explanation = explanation,
+        ));
+    }
+
+    text.push('\n');
+    text.push_str(&theme.footer.markup("Press any key to close the help."));
+    text
+}
+
+/// The width of an entry on screen, in columns.
+fn entry_width(entry: &Entry, settings: &SettingsAccumulator) -> usize {
+    markup::width(&render_entry(entry, settings, None))
+}
+
+/// Renders an entry in the entry format of the settings, highlighted if it is selected.
+fn render_entry(entry: &Entry, settings: &SettingsAccumulator, selected: Option<char>) -> String {
+    let theme = settings.with_entry(entry).theme;
+    let text = format_entry(
+        &settings.entry_format,
+        &theme
+            .shortcut
+            .markup(&markup::escape(&entry.shortcut().to_string())),
+        &theme.entry_title.markup(entry.title()),
+        entry
+            .icon()
+            .map(|icon| theme.entry_title.markup(icon))
+            .as_deref(),
+    );
+    // Text in the format itself, like brackets around the key, gets the brackets style.
+    let text = theme.brackets.markup(&text);
+
+    if selected == Some(entry.shortcut()) {
+        theme.selected.markup(&text)
+    } else {
+        text
+    }
+}
+
+/// Replaces `{key}`, `{title}` and `{icon}` in an entry format. When there is no icon, a space
+/// after its placeholder is removed too, so that formats like `{icon} {title}` work for entries
+/// with and without icons. Anything else in the format is kept as-is, including markup.
+fn format_entry(format: &str, key: &str, title: &str, icon: Option<&str>) -> String {
+    let mut text = String::new();
+    let mut rest = format;
+
+    while let Some(chr) = rest.chars().next() {
+        if chr == '\\' {
+            // Keep escaped characters, so that `\{key}` is not replaced.
+            let length = rest.chars().take(2).map(char::len_utf8).sum();
+            text.push_str(&rest[..length]);
+            rest = &rest[length..];
+        } else if let Some(after) = rest.strip_prefix("{key}") {
+            text.push_str(key);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{title}") {
+            text.push_str(title);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{icon}") {
+            match icon {
+                Some(icon) => {
+                    text.push_str(icon);
+                    rest = after;
+                }
+                None => rest = after.strip_prefix(' ').unwrap_or(after),
+            }
+        } else {
+            text.push(chr);
+            rest = &rest[chr.len_utf8()..];
+        }
+    }
+
+    text
 }
 
-fn render_entry_color(entry: &Entry, settings: &SettingsAccumulator) -> String {
-    let settings = settings.with_entry(entry);
-    format!(
-        "[{{fg={color} {shortcut}}}] {title}  ",
-        shortcut = entry.shortcut(),
-        title = entry.title(),
-        color = settings.shortcut_color.markup_name()
-    )
+/// The separator between entries, in the separator style.
+fn render_separator(settings: &SettingsAccumulator) -> String {
+    settings
+        .theme
+        .separator
+        .markup(&markup::escape(&settings.entry_separator))
 }
 
 fn required_lines_option(option: Option<&str>, max_width: usize) -> usize {
@@ -189,11 +940,348 @@ fn required_lines_option(option: Option<&str>, max_width: usize) -> usize {
     }
 }
 
-fn required_lines(string: &str, max_width: usize) -> usize {
-    // TODO: Do real calculation with word-wrap (instead of char-wrap), and markup strings removed.
-    // This is a very naive implementation right now.
-    string
-        .split('\n')
-        .map(|line| (line.len() / max_width) + 1)
-        .sum()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_columns_into_rows_that_fit() {
+        assert_eq!(grid_rows(&[10, 10, 10], 30), vec![0..3]);
+        assert_eq!(grid_rows(&[10, 10, 10], 25), vec![0..2, 2..3]);
+        assert_eq!(grid_rows(&[20, 10, 15, 5], 25), vec![0..1, 1..3, 3..4]);
+        assert_eq!(grid_rows(&[30, 10], 25), vec![0..1, 1..2]);
+        assert_eq!(grid_rows(&[], 25), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn it_measures_columns_by_their_width_on_screen() {
+        let group: Group = serde_yaml::from_str(
+            r#"
+title: Mixed
+entries:
+  - {shortcut: a, title: Café, command: "true"}
+  - {shortcut: b, title: 日本語, command: "true"}
+  - {shortcut: c, title: "{fg=red Red}", command: "true"}"#,
+        )
+        .unwrap();
+
+        let settings = SettingsAccumulator::default();
+        let boxed = SettingsAccumulator {
+            boxed: true,
+            ..Default::default()
+        };
+
+        // "[b] 日本語  " takes 12 columns, while it is 15 bytes long.
+        assert_eq!(column_width(&group, &settings), 12);
+        assert_eq!(column_width(&group, &boxed), 14);
+        assert_eq!(column_height(&group, &settings, 12), 4);
+        // Too narrow for "日本語" next to its shortcut, so it wraps to its own line.
+        assert_eq!(column_height(&group, &settings, 8), 5);
+    }
+
+    #[test]
+    fn it_formats_entries() {
+        let group: Group = serde_yaml::from_str(
+            r#"
+entries:
+  - {shortcut: a, title: Apple, icon: "", command: "true"}
+  - {shortcut: b, title: Banana, command: "true"}"#,
+        )
+        .unwrap();
+        let settings = SettingsAccumulator {
+            entry_format: String::from("{icon} {title} ({key}) \\{key}"),
+            entry_separator: String::from(" | "),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            markup::plain(&list_entries_text(&group, &settings, None, 80)),
+            " Apple (a) {key} | Banana (b) {key}"
+        );
+        assert_eq!(
+            markup::plain(&list_entries_text(&group, &settings, None, 30)),
+            " Apple (a) {key}\nBanana (b) {key}"
+        );
+        // The separator is the gap between columns.
+        assert_eq!(column_width(&group, &settings), 19);
+    }
+
+    #[test]
+    fn it_explains_entries_and_keys_in_the_help() {
+        let page: Page = serde_yaml::from_str(
+            r#"
+title: Menu
+groups:
+  - title: Things
+    entries:
+      - shortcut: a
+        title: Apple
+        description: Eats an {apple}.
+        mode: wait
+        return: true
+        command: echo apple
+      - shortcut: "?"
+        title: Questions
+        return: faq"#,
+        )
+        .unwrap();
+        let settings = SettingsAccumulator::default();
+
+        assert_eq!(
+            markup::plain(&help_text(&page, &settings, Some("Apple"))),
+            "\
+Things:
+[a] Apple  (wait, return: same page)
+    Eats an {apple}.
+    $ echo apple
+[?] Questions  (normal, return: page faq)
+
+Keys:
+[.] Run Apple again
+[Esc] Exit tydra
+[Ctrl-L] Redraw the menu
+[Ctrl-Z] Suspend tydra
+
+Press any key to close the help."
+        );
+    }
+
+    #[test]
+    fn it_shows_context_in_the_status_bar() {
+        use crate::status::RunResult;
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+        use std::time::Duration;
+
+        let page: Page = serde_yaml::from_str("title: Git\ngroups: []").unwrap();
+        let settings = SettingsAccumulator::default();
+        let mut status = Status::new("root");
+        status.go_to_page("git");
+        status.last_run = Some(RunResult {
+            exit_status: ExitStatus::from_raw(1 << 8),
+            duration: Duration::from_millis(20),
+        });
+
+        assert_eq!(
+            markup::plain(&status_bar_text(&page, &settings, &status, 60)),
+            " root › git │ ✗ Exited with 1 after 20ms   ? help  Esc quit "
+        );
+
+        // The entry that the repeat key runs again is shown with the hints.
+        status.repeat = Some(String::from("Pull"));
+        assert_eq!(
+            markup::plain(&status_bar_text(&page, &settings, &status, 76)),
+            " root › git │ ✗ Exited with 1 after 20ms   . repeat: Pull  ? help  Esc quit "
+        );
+        status.repeat = None;
+
+        // Hints are left out when they do not fit.
+        status.error("Could not parse {file}");
+        assert_eq!(
+            markup::plain(&status_bar_text(&page, &settings, &status, 60)),
+            " root › git │ ✗ Exited with 1 after 20ms │ Could not parse {file}"
+        );
+    }
+
+    #[test]
+    fn it_picks_the_densest_layout_that_fits_for_auto() {
+        let page: Page = serde_yaml::from_str(
+            r#"
+title: Menu
+groups:
+  - title: Fruits
+    entries:
+      - {shortcut: a, title: Apple, command: "true"}
+      - {shortcut: b, title: Banana, command: "true"}
+  - title: Vegetables
+    entries:
+      - {shortcut: c, title: Carrot, command: "true"}
+      - {shortcut: l, title: Leek, command: "true"}
+  - title: Grains
+    entries:
+      - {shortcut: r, title: Rice, command: "true"}
+      - {shortcut: o, title: Oats, command: "true"}"#,
+        )
+        .unwrap();
+        let settings = SettingsAccumulator {
+            layout: Layout::Auto,
+            ..Default::default()
+        };
+        let layout = |width, height| effective_layout(&page, &settings, width, height);
+
+        // All groups fit next to each other.
+        assert_eq!(layout(80, 24), Layout::Columns);
+        // The groups must be split into rows to fit the width.
+        assert_eq!(layout(30, 24), Layout::Grid);
+        // The groups are too wide to be shown as columns at all.
+        assert_eq!(layout(10, 24), Layout::List);
+        // Nothing fits the height, so the layout that needs the fewest lines is used.
+        assert_eq!(layout(80, 2), Layout::Columns);
+        assert_eq!(layout(30, 2), Layout::Grid);
+    }
+
+    #[test]
+    fn it_places_menus_at_their_position() {
+        let page: Page = serde_yaml::from_str(
+            r#"
+title: Menu
+groups:
+  - entries:
+      - shortcut: a
+        title: Apple
+        command: "true""#,
+        )
+        .unwrap();
+        let screen = Rect::new(0, 0, 40, 20);
+        let settings = |boxed, position| SettingsAccumulator {
+            boxed,
+            position,
+            ..Default::default()
+        };
+
+        let top = settings(false, Position::Top);
+        assert_eq!(menu_area(screen, &page, &top, Layout::List), screen);
+
+        let bottom = settings(false, Position::Bottom);
+        assert_eq!(
+            menu_area(screen, &page, &bottom, Layout::List),
+            Rect::new(0, 17, 40, 3)
+        );
+
+        // "[a] Apple" is 9 wide, and the group box and page box both add two lines and two
+        // columns.
+        let center = settings(true, Position::Center);
+        assert_eq!(
+            menu_area(screen, &page, &center, Layout::List),
+            Rect::new(13, 7, 13, 5)
+        );
+    }
+
+    #[test]
+    fn it_highlights_the_selected_entry_with_the_default_theme() {
+        use crate::backend::HeadlessBackend;
+        use tui::style::Modifier;
+
+        let page: Page = serde_yaml::from_str(
+            r#"
+title: Fruit
+groups:
+  - entries:
+      - shortcut: a
+        title: Apple
+      - shortcut: b
+        title: Banana"#,
+        )
+        .unwrap();
+        let settings = SettingsAccumulator::default();
+        let mut term = Terminal::new(HeadlessBackend::new(40, 10)).unwrap();
+        render(&mut term, &page, &settings, Some('b'), &Status::new("root")).unwrap();
+
+        let backend = term.backend();
+        let position_of = |title: &str| {
+            let text = backend.text();
+            text.lines()
+                .enumerate()
+                .find_map(|(y, line)| line.find(title).map(|x| (x as u16, y as u16)))
+                .unwrap()
+        };
+        let (x, y) = position_of("Banana");
+        assert_eq!(backend.cell(x, y).style.modifier, Modifier::Bold);
+        let (x, y) = position_of("Apple");
+        assert_eq!(backend.cell(x, y).style.modifier, Modifier::Reset);
+    }
+
+    /// Golden files in `tests/golden` hold what every page of the fixtures and example action
+    /// files looks like at a few terminal sizes. Run the tests with `UPDATE_GOLDEN=1` to write
+    /// them again after changing how menus are rendered, and review the difference.
+    mod golden {
+        use super::*;
+        use crate::actions::ActionFile;
+        use crate::backend::HeadlessBackend;
+        use std::fs;
+        use std::path::{Path, PathBuf};
+
+        const SIZES: [(u16, u16); 3] = [(80, 24), (40, 16), (120, 10)];
+
+        /// The layouts to render the menus in, named like their golden files. Layouts with a
+        /// position are boxed and shown at that position.
+        const LAYOUTS: [(&str, Layout, Option<Position>); 6] = [
+            ("list", Layout::List, None),
+            ("columns", Layout::Columns, None),
+            ("grid", Layout::Grid, None),
+            ("auto", Layout::Auto, None),
+            ("boxed-list-center", Layout::List, Some(Position::Center)),
+            ("boxed-grid-bottom", Layout::Grid, Some(Position::Bottom)),
+        ];
+
+        #[test]
+        fn it_renders_menus_like_the_golden_files() {
+            let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+            let mut mismatches = Vec::new();
+
+            let sources = [("fixtures", "tests/fixtures"), ("examples", "doc/examples")];
+            for (prefix, directory) in &sources {
+                for path in action_files(&root.join(directory)) {
+                    let stem = path.file_stem().unwrap().to_string_lossy();
+                    for &(layout_name, layout, position) in &LAYOUTS {
+                        let name = format!("{}-{}-{}.txt", prefix, stem, layout_name);
+                        let golden = root.join("tests/golden").join(&name);
+                        let actual = render_action_file(&path, layout, position);
+
+                        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+                            fs::write(&golden, &actual).unwrap();
+                        } else if fs::read_to_string(&golden).ok().as_ref() != Some(&actual) {
+                            mismatches.push(name);
+                        }
+                    }
+                }
+            }
+
+            assert!(
+                mismatches.is_empty(),
+                "Rendering does not match the golden files {:?}. Run the tests with \
+                 UPDATE_GOLDEN=1 to update them.",
+                mismatches
+            );
+        }
+
+        fn action_files(directory: &Path) -> Vec<PathBuf> {
+            let mut paths: Vec<PathBuf> = fs::read_dir(directory)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "yml"))
+                .collect();
+            paths.sort();
+            paths
+        }
+
+        /// Renders every page of the action file at every size, one after another.
+        fn render_action_file(path: &Path, layout: Layout, position: Option<Position>) -> String {
+            let mut actions: ActionFile =
+                serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            actions.resolve_themes(path.parent().unwrap()).unwrap();
+
+            let mut pages: Vec<(&Page, &str)> = actions.pages_with_names().collect();
+            pages.sort_by_key(|&(_, name)| name);
+
+            let mut output = String::new();
+            for (page, name) in pages {
+                let mut settings = SettingsAccumulator {
+                    layout,
+                    ..actions.settings_accumulator().with_page(page)
+                };
+                if let Some(position) = position {
+                    settings.boxed = true;
+                    settings.position = position;
+                }
+                for &(width, height) in &SIZES {
+                    let mut term = Terminal::new(HeadlessBackend::new(width, height)).unwrap();
+                    render(&mut term, page, &settings, None, &Status::new(name)).unwrap();
+                    output.push_str(&format!("=== {} at {}x{} ===\n", name, width, height));
+                    output.push_str(&term.backend().text());
+                }
+            }
+            output
+        }
+    }
 }
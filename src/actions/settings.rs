@@ -1,37 +1,60 @@
+use super::style::Color;
+use super::theme::{Theme, ThemeSetting};
 use super::{Entry, Group, Page};
+use failure::Error;
+use schemars::JsonSchema;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Settings {
     layout: Option<Layout>,
     shortcut_color: Option<Color>,
+    theme: Option<ThemeSetting>,
+    boxed: Option<bool>,
+    position: Option<Position>,
+    entry_format: Option<String>,
+    entry_separator: Option<String>,
+    status_bar: Option<bool>,
+    highlight_last_used: Option<bool>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct SettingsAccumulator {
     pub layout: Layout,
-    pub shortcut_color: Color,
+    pub theme: Theme,
+    pub boxed: bool,
+    pub position: Position,
+    pub entry_format: String,
+    pub entry_separator: String,
+    pub status_bar: bool,
+    pub highlight_last_used: bool,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// How entries are shown, with `{key}`, `{title}` and `{icon}` replaced by those of the entry.
+pub const DEFAULT_ENTRY_FORMAT: &str = "[{key}] {title}";
+
+/// What is shown between entries.
+pub const DEFAULT_ENTRY_SEPARATOR: &str = "  ";
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Layout {
+    #[default]
     List,
     Columns,
+    Grid,
+    Auto,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+/// Where on the screen the menu is shown.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
-pub enum Color {
-    Reset,
-    Black,
-    Blue,
-    Cyan,
-    Green,
-    Magenta,
-    Red,
-    White,
-    Yellow,
+pub enum Position {
+    #[default]
+    Top,
+    Bottom,
+    Center,
 }
 
 impl Default for Settings {
@@ -39,15 +62,78 @@ impl Default for Settings {
         Settings {
             shortcut_color: Some(Color::Red),
             layout: Some(Layout::default()),
+            theme: None,
+            boxed: Some(false),
+            position: Some(Position::default()),
+            entry_format: Some(String::from(DEFAULT_ENTRY_FORMAT)),
+            entry_separator: Some(String::from(DEFAULT_ENTRY_SEPARATOR)),
+            status_bar: Some(false),
+            highlight_last_used: Some(false),
+        }
+    }
+}
+
+impl Default for SettingsAccumulator {
+    fn default() -> SettingsAccumulator {
+        SettingsAccumulator {
+            layout: Layout::default(),
+            theme: Theme::bundled_default(),
+            boxed: false,
+            position: Position::default(),
+            entry_format: String::from(DEFAULT_ENTRY_FORMAT),
+            entry_separator: String::from(DEFAULT_ENTRY_SEPARATOR),
+            status_bar: false,
+            highlight_last_used: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the theme, and every theme it is based on, so it is ready to be used when
+    /// rendering. Theme files are looked up relative to the given directory, and their paths are
+    /// added to `files`.
+    pub fn resolve_theme(
+        &mut self,
+        directory: &Path,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<(), Error> {
+        if let Some(ref setting) = self.theme {
+            self.theme = Some(setting.resolve(directory, files)?.into());
         }
+        Ok(())
     }
 }
 
 impl SettingsAccumulator {
+    /// Settings are applied in order, so `shortcut_color` overrides the foreground color of the
+    /// shortcut style in the same settings.
     pub fn with_settings(&self, settings: &Settings) -> SettingsAccumulator {
+        // Themes are resolved when the action file is loaded, see `Settings::resolve_theme`.
+        let mut theme = match settings.theme.as_ref().and_then(ThemeSetting::resolved) {
+            Some(theme) => self.theme.merge(theme),
+            None => self.theme,
+        };
+        if let Some(color) = settings.shortcut_color {
+            theme.shortcut.fg = Some(color);
+        }
+
         SettingsAccumulator {
             layout: settings.layout.unwrap_or(self.layout),
-            shortcut_color: settings.shortcut_color.unwrap_or(self.shortcut_color),
+            theme,
+            boxed: settings.boxed.unwrap_or(self.boxed),
+            position: settings.position.unwrap_or(self.position),
+            entry_format: settings
+                .entry_format
+                .clone()
+                .unwrap_or_else(|| self.entry_format.clone()),
+            entry_separator: settings
+                .entry_separator
+                .clone()
+                .unwrap_or_else(|| self.entry_separator.clone()),
+            status_bar: settings.status_bar.unwrap_or(self.status_bar),
+            highlight_last_used: settings
+                .highlight_last_used
+                .unwrap_or(self.highlight_last_used),
         }
     }
 
@@ -66,9 +152,14 @@ impl SettingsAccumulator {
     }
 
     pub fn with_entry(&self, entry: &Entry) -> SettingsAccumulator {
+        let mut theme = self.theme;
+        if let Some(color) = entry.shortcut_color() {
+            theme.shortcut.fg = Some(color);
+        }
+
         SettingsAccumulator {
-            layout: self.layout,
-            shortcut_color: entry.shortcut_color().unwrap_or(self.shortcut_color),
+            theme,
+            ..self.clone()
         }
     }
 
@@ -79,51 +170,16 @@ impl SettingsAccumulator {
 
 impl<'a> From<&'a Settings> for SettingsAccumulator {
     fn from(settings: &'a Settings) -> SettingsAccumulator {
-        let default_settings = Settings::default();
-        SettingsAccumulator {
-            layout: settings
-                .layout
-                .or(default_settings.layout)
-                .unwrap_or_default(),
-            shortcut_color: settings
-                .shortcut_color
-                .or(default_settings.shortcut_color)
-                .unwrap_or_default(),
-        }
-    }
-}
-
-impl Default for Layout {
-    fn default() -> Layout {
-        Layout::List
-    }
-}
-
-impl Default for Color {
-    fn default() -> Color {
-        Color::Reset
-    }
-}
-
-impl Color {
-    pub fn markup_name(self) -> &'static str {
-        match self {
-            Color::Reset => "reset",
-            Color::Black => "black",
-            Color::Blue => "blue",
-            Color::Cyan => "cyan",
-            Color::Green => "green",
-            Color::Magenta => "magenta",
-            Color::Red => "red",
-            Color::White => "white",
-            Color::Yellow => "yellow",
-        }
+        SettingsAccumulator::default()
+            .with_settings(&Settings::default())
+            .with_settings(settings)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::actions::TextStyle;
 
     #[test]
     fn it_has_sane_defaults() {
@@ -137,19 +193,38 @@ mod tests {
         let settings1 = Settings {
             layout: Some(Layout::Columns),
             shortcut_color: Some(Color::Green),
+            theme: None,
+            boxed: Some(true),
+            position: Some(Position::Center),
+            entry_format: Some(String::from("{key} → {title}")),
+            entry_separator: None,
+            status_bar: None,
+            highlight_last_used: None,
         };
         let settings2 = Settings {
             layout: None,
             shortcut_color: Some(Color::Yellow),
+            theme: None,
+            boxed: None,
+            position: None,
+            entry_format: None,
+            entry_separator: Some(String::from(" | ")),
+            status_bar: Some(true),
+            highlight_last_used: Some(true),
         };
 
         let accumulator = SettingsAccumulator::from(&settings1);
         assert_eq!(accumulator.layout, Layout::Columns);
-        assert_eq!(accumulator.shortcut_color, Color::Green);
+        assert_eq!(accumulator.theme.shortcut.fg, Some(Color::Green));
+        assert!(accumulator.boxed);
 
         let accumulator = accumulator.with_settings(&settings2);
         assert_eq!(accumulator.layout, Layout::Columns);
-        assert_eq!(accumulator.shortcut_color, Color::Yellow);
+        assert_eq!(accumulator.theme.shortcut.fg, Some(Color::Yellow));
+        assert_eq!(accumulator.position, Position::Center);
+        assert_eq!(accumulator.entry_format, "{key} → {title}");
+        assert_eq!(accumulator.entry_separator, " | ");
+        assert!(accumulator.highlight_last_used);
     }
 
     #[test]
@@ -159,13 +234,79 @@ mod tests {
         let blank_settings = Settings {
             layout: None,
             shortcut_color: None,
+            theme: None,
+            boxed: None,
+            position: None,
+            entry_format: None,
+            entry_separator: None,
+            status_bar: None,
+            highlight_last_used: None,
         };
 
         let accumulator = SettingsAccumulator::from(&blank_settings);
         assert_eq!(accumulator.layout, default_settings.layout.unwrap());
         assert_eq!(
-            accumulator.shortcut_color,
-            default_settings.shortcut_color.unwrap()
+            accumulator.theme.shortcut.fg,
+            default_settings.shortcut_color
+        );
+    }
+
+    #[test]
+    fn it_uses_the_default_theme_when_no_theme_is_set() {
+        let accumulator = SettingsAccumulator::from(&Settings::default());
+        assert!(!accumulator.theme.selected.is_empty());
+        assert_eq!(accumulator.theme, Theme::bundled_default());
+    }
+
+    #[test]
+    fn it_accumulates_themes() {
+        extern crate serde_yaml;
+        use crate::actions::style::Attribute;
+
+        let settings1: Settings = serde_yaml::from_str(
+            r##"
+theme:
+  title: {fg: "#ff8800", attribute: bold}
+  shortcut: {fg: blue, bg: 236}"##,
+        )
+        .unwrap();
+        let settings2: Settings = serde_yaml::from_str(
+            r##"
+shortcut_color: bright_green
+theme:
+  title: {attribute: underline}
+  shortcut: {fg: yellow}"##,
+        )
+        .unwrap();
+
+        let accumulator = SettingsAccumulator::from(&settings1);
+        assert_eq!(
+            accumulator.theme.title,
+            TextStyle {
+                fg: Some(Color::Rgb(255, 136, 0)),
+                bg: None,
+                attribute: Some(Attribute::Bold),
+            }
+        );
+        assert_eq!(accumulator.theme.shortcut.fg, Some(Color::Blue));
+        assert_eq!(accumulator.theme.group_title, TextStyle::default());
+
+        let accumulator = accumulator.with_settings(&settings2);
+        assert_eq!(
+            accumulator.theme.title,
+            TextStyle {
+                fg: Some(Color::Rgb(255, 136, 0)),
+                bg: None,
+                attribute: Some(Attribute::Underline),
+            }
+        );
+        assert_eq!(
+            accumulator.theme.shortcut,
+            TextStyle {
+                fg: Some(Color::BrightGreen),
+                bg: Some(Color::Indexed(236)),
+                attribute: None,
+            }
         );
     }
 }
@@ -0,0 +1,746 @@
+extern crate serde;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation, SubschemaValidation};
+use schemars::JsonSchema;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tui::style::{Color as TuiColor, Modifier, Style};
+
+/// A color, either one of the standard terminal colors, an index in the 256-color palette, or a
+/// true color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    #[default]
+    Reset,
+    Black,
+    Blue,
+    Cyan,
+    Green,
+    Magenta,
+    Red,
+    White,
+    Yellow,
+    BrightBlack,
+    BrightBlue,
+    BrightCyan,
+    BrightGreen,
+    BrightMagenta,
+    BrightRed,
+    BrightWhite,
+    BrightYellow,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// A text attribute. Only one attribute can be set for each piece of text, as tui only keeps a
+/// single `Modifier` in each cell of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribute {
+    Bold,
+    Dim,
+    Italic,
+    Underline,
+    Invert,
+    CrossedOut,
+}
+
+/// How some text should look. Unset properties are inherited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TextStyle {
+    /// Foreground (text) color.
+    pub fg: Option<Color>,
+    /// Background color.
+    pub bg: Option<Color>,
+    /// Text attribute, like bold or underline.
+    pub attribute: Option<Attribute>,
+}
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// No colors at all, as requested through `$NO_COLOR`. Attributes are still shown.
+    NoColor,
+    /// The 16 standard colors.
+    Basic,
+    /// The 256-color palette: the standard colors, a 6x6x6 color cube and a grayscale ramp.
+    Palette,
+    /// Full 24-bit colors.
+    TrueColor,
+}
+
+const COLOR_NAMES: [(&str, Color); 17] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("blue", Color::Blue),
+    ("cyan", Color::Cyan),
+    ("green", Color::Green),
+    ("magenta", Color::Magenta),
+    ("red", Color::Red),
+    ("white", Color::White),
+    ("yellow", Color::Yellow),
+    ("bright_black", Color::BrightBlack),
+    ("bright_blue", Color::BrightBlue),
+    ("bright_cyan", Color::BrightCyan),
+    ("bright_green", Color::BrightGreen),
+    ("bright_magenta", Color::BrightMagenta),
+    ("bright_red", Color::BrightRed),
+    ("bright_white", Color::BrightWhite),
+    ("bright_yellow", Color::BrightYellow),
+];
+
+/// The standard colors in the order of their 256-color palette index, along with their
+/// approximate RGB values (as in xterm).
+const STANDARD_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+    (Color::BrightBlack, (127, 127, 127)),
+    (Color::BrightRed, (255, 0, 0)),
+    (Color::BrightGreen, (0, 255, 0)),
+    (Color::BrightYellow, (255, 255, 0)),
+    (Color::BrightBlue, (92, 92, 255)),
+    (Color::BrightMagenta, (255, 0, 255)),
+    (Color::BrightCyan, (0, 255, 255)),
+    (Color::BrightWhite, (255, 255, 255)),
+];
+
+impl Color {
+    /// Converts the color to one that the terminal can show.
+    pub fn to_tui(self, support: ColorSupport) -> TuiColor {
+        match (self, support) {
+            (_, ColorSupport::NoColor) | (Color::Reset, _) => TuiColor::Reset,
+            (Color::Indexed(index), _) if index < 16 => {
+                STANDARD_COLORS[index as usize].0.to_tui(support)
+            }
+            // tui has no palette colors, so they are passed on as their RGB values. The
+            // ScreenBackend turns those back into palette indexes for terminals that need them.
+            (Color::Indexed(index), ColorSupport::Palette)
+            | (Color::Indexed(index), ColorSupport::TrueColor) => {
+                let (r, g, b) = palette_rgb(index);
+                TuiColor::Rgb(r, g, b)
+            }
+            (Color::Indexed(index), ColorSupport::Basic) => {
+                nearest_standard_color(palette_rgb(index)).to_tui(support)
+            }
+            (Color::Rgb(r, g, b), ColorSupport::TrueColor) => TuiColor::Rgb(r, g, b),
+            (Color::Rgb(r, g, b), ColorSupport::Palette) => {
+                let (r, g, b) = palette_rgb(cube_index((r, g, b)));
+                TuiColor::Rgb(r, g, b)
+            }
+            (Color::Rgb(r, g, b), ColorSupport::Basic) => {
+                nearest_standard_color((r, g, b)).to_tui(support)
+            }
+            (Color::Black, _) => TuiColor::Black,
+            (Color::Blue, _) => TuiColor::Blue,
+            (Color::Cyan, _) => TuiColor::Cyan,
+            (Color::Green, _) => TuiColor::Green,
+            (Color::Magenta, _) => TuiColor::Magenta,
+            (Color::Red, _) => TuiColor::Red,
+            (Color::White, _) => TuiColor::Gray,
+            (Color::Yellow, _) => TuiColor::Yellow,
+            (Color::BrightBlack, _) => TuiColor::DarkGray,
+            (Color::BrightBlue, _) => TuiColor::LightBlue,
+            (Color::BrightCyan, _) => TuiColor::LightCyan,
+            (Color::BrightGreen, _) => TuiColor::LightGreen,
+            (Color::BrightMagenta, _) => TuiColor::LightMagenta,
+            (Color::BrightRed, _) => TuiColor::LightRed,
+            (Color::BrightWhite, _) => TuiColor::White,
+            (Color::BrightYellow, _) => TuiColor::LightYellow,
+        }
+    }
+}
+
+/// RGB values of the colors in the 256-color palette, past the 16 standard colors.
+fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    if index < 16 {
+        STANDARD_COLORS[index as usize].1
+    } else if index < 232 {
+        // 6x6x6 color cube
+        let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+        let index = index - 16;
+        (level(index / 36), level((index / 6) % 6), level(index % 6))
+    } else {
+        // Grayscale ramp
+        let value = 8 + (index - 232) * 10;
+        (value, value, value)
+    }
+}
+
+/// The levels of each component in the 6x6x6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The palette index of the color in the 6x6x6 color cube that is closest to the RGB value.
+fn cube_index((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |value: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (i32::from(**level) - i32::from(value)).abs())
+            .map(|(index, _)| index as u8)
+            .unwrap_or(0)
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// The palette index for an RGB value, for terminals that only understand palette colors.
+///
+/// Palette colors are turned into their exact RGB values by `Color::to_tui`, so those get their
+/// original index back. Anything else gets the closest color in the color cube.
+pub fn palette_index(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255)
+        .find(|index| palette_rgb(*index) == rgb)
+        .unwrap_or_else(|| cube_index(rgb))
+}
+
+fn nearest_standard_color((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        let square = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        square(r, r2) + square(g, g2) + square(b, b2)
+    };
+    STANDARD_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or_default()
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    /// Parses a color name, a hex code like "#ff8800", or a 256-color palette index.
+    fn from_str(string: &str) -> Result<Color, String> {
+        if let Some(&(_, color)) = COLOR_NAMES.iter().find(|(name, _)| *name == string) {
+            return Ok(color);
+        }
+
+        if let Some(hex) = string.strip_prefix('#') {
+            let component = |range| {
+                hex.get(range)
+                    .and_then(|part| u8::from_str_radix(part, 16).ok())
+            };
+            if hex.len() == 6 {
+                if let (Some(r), Some(g), Some(b)) =
+                    (component(0..2), component(2..4), component(4..6))
+                {
+                    return Ok(Color::Rgb(r, g, b));
+                }
+            }
+            return Err(format!("Invalid hex color: {}", string));
+        }
+
+        string
+            .parse::<u8>()
+            .map(Color::Indexed)
+            .map_err(|_| format!("Unknown color: {}", string))
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats the color the same way it can be parsed.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Indexed(index) => write!(formatter, "{}", index),
+            Color::Rgb(r, g, b) => write!(formatter, "#{:02x}{:02x}{:02x}", r, g, b),
+            color => {
+                let name = COLOR_NAMES
+                    .iter()
+                    .find(|(_, other)| *other == color)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("reset");
+                formatter.write_str(name)
+            }
+        }
+    }
+}
+
+/// Parse a color name or hex code from a string, or a palette index from a number.
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a color name, a hex color like \"#ff8800\", or a number from 0 to 255")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Color, E>
+    where
+        E: de::Error,
+    {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Color, E>
+    where
+        E: de::Error,
+    {
+        if value <= 255 {
+            Ok(Color::Indexed(value as u8))
+        } else {
+            Err(E::custom(format!("Color index out of range: {}", value)))
+        }
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Color, E>
+    where
+        E: de::Error,
+    {
+        if value >= 0 {
+            self.visit_u64(value as u64)
+        } else {
+            Err(E::custom(format!("Color index out of range: {}", value)))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Color, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+impl JsonSchema for Color {
+    fn schema_name() -> String {
+        String::from("Color")
+    }
+
+    /// Mirrors the ColorVisitor: a name, a hex code, or a palette index.
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        let names = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(COLOR_NAMES.iter().map(|(name, _)| (*name).into()).collect()),
+            ..Default::default()
+        };
+        let hex = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(String::from("^#[0-9a-fA-F]{6}$")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        let mut index = SchemaObject {
+            instance_type: Some(InstanceType::Integer.into()),
+            ..Default::default()
+        };
+        index.number().minimum = Some(0.0);
+        index.number().maximum = Some(255.0);
+
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![names.into(), hex.into(), index.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl Attribute {
+    pub fn to_tui(self) -> Modifier {
+        match self {
+            Attribute::Bold => Modifier::Bold,
+            Attribute::Dim => Modifier::Faint,
+            Attribute::Italic => Modifier::Italic,
+            Attribute::Underline => Modifier::Underline,
+            Attribute::Invert => Modifier::Invert,
+            Attribute::CrossedOut => Modifier::CrossedOut,
+        }
+    }
+
+    pub fn markup_name(self) -> &'static str {
+        match self {
+            Attribute::Bold => "bold",
+            Attribute::Dim => "dim",
+            Attribute::Italic => "italic",
+            Attribute::Underline => "underline",
+            Attribute::Invert => "invert",
+            Attribute::CrossedOut => "crossed_out",
+        }
+    }
+}
+
+impl FromStr for Attribute {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Attribute, String> {
+        match string {
+            "bold" => Ok(Attribute::Bold),
+            "dim" => Ok(Attribute::Dim),
+            "italic" => Ok(Attribute::Italic),
+            "underline" => Ok(Attribute::Underline),
+            "invert" => Ok(Attribute::Invert),
+            "crossed_out" => Ok(Attribute::CrossedOut),
+            other => Err(format!("Unknown attribute: {}", other)),
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn fg(color: Color) -> TextStyle {
+        TextStyle {
+            fg: Some(color),
+            ..TextStyle::default()
+        }
+    }
+
+    /// Returns a new style where everything set in the other style replaces the values in this
+    /// one.
+    pub fn merge(&self, other: &TextStyle) -> TextStyle {
+        TextStyle {
+            fg: other.fg.or(self.fg),
+            bg: other.bg.or(self.bg),
+            attribute: other.attribute.or(self.attribute),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fg.is_none() && self.bg.is_none() && self.attribute.is_none()
+    }
+
+    /// Wraps the text in markup for this style.
+    pub fn markup(&self, text: &str) -> String {
+        if self.is_empty() {
+            return text.to_owned();
+        }
+
+        let mut commands = Vec::new();
+        if let Some(fg) = self.fg {
+            commands.push(format!("fg={}", fg));
+        }
+        if let Some(bg) = self.bg {
+            commands.push(format!("bg={}", bg));
+        }
+        if let Some(attribute) = self.attribute {
+            commands.push(format!("mod={}", attribute.markup_name()));
+        }
+        format!("{{{} {}}}", commands.join(";"), text)
+    }
+
+    /// Applies this style on top of a terminal style.
+    pub fn apply(&self, style: Style, support: ColorSupport) -> Style {
+        let mut style = style;
+        if let Some(fg) = self.fg {
+            style.fg = fg.to_tui(support);
+        }
+        if let Some(bg) = self.bg {
+            style.bg = bg.to_tui(support);
+        }
+        if let Some(attribute) = self.attribute {
+            style.modifier = attribute.to_tui();
+        }
+        style
+    }
+}
+
+impl ColorSupport {
+    /// Detects color support of the current terminal, once.
+    pub fn current() -> ColorSupport {
+        static SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
+        *SUPPORT.get_or_init(|| {
+            let var = |name| std::env::var(name).ok();
+            let term = var("TERM");
+            let colors = term.as_deref().and_then(terminfo_colors);
+            ColorSupport::detect(var("NO_COLOR"), var("COLORTERM"), term, colors)
+        })
+    }
+
+    /// Decides on color support from the environment.
+    ///
+    /// `$NO_COLOR` disables colors when set to anything (see https://no-color.org), and
+    /// `$COLORTERM` is what most terminals use to announce 24-bit color support. Terminals with
+    /// the 256-color palette are found by their `$TERM`, like *xterm-256color*, or by the number
+    /// of colors in their terminfo entry.
+    pub fn detect(
+        no_color: Option<String>,
+        colorterm: Option<String>,
+        term: Option<String>,
+        terminfo_colors: Option<u32>,
+    ) -> ColorSupport {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::NoColor;
+        }
+
+        if term.as_deref() == Some("dumb") {
+            return ColorSupport::NoColor;
+        }
+
+        match colorterm.as_deref() {
+            Some("truecolor") | Some("24bit") => ColorSupport::TrueColor,
+            _ if term.is_some_and(|term| term.contains("256color")) => ColorSupport::Palette,
+            _ if terminfo_colors.is_some_and(|colors| colors >= 256) => ColorSupport::Palette,
+            _ => ColorSupport::Basic,
+        }
+    }
+}
+
+/// Directories that compiled terminfo entries are looked up in, in order.
+fn terminfo_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+    if let Some(directory) = std::env::var_os("TERMINFO") {
+        directories.push(PathBuf::from(directory));
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        directories.push(Path::new(&home).join(".terminfo"));
+    }
+    if let Some(dirs) = std::env::var_os("TERMINFO_DIRS") {
+        directories.extend(std::env::split_paths(&dirs).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    for directory in &["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+        directories.push(PathBuf::from(directory));
+    }
+    directories
+}
+
+/// Reads the number of colors from the terminfo entry of the terminal, if there is one.
+fn terminfo_colors(term: &str) -> Option<u32> {
+    let first = term.chars().next()?;
+    terminfo_directories().into_iter().find_map(|directory| {
+        // Entries are sorted by their first letter, or by its hex code on some systems.
+        [first.to_string(), format!("{:x}", first as u32)]
+            .iter()
+            .find_map(|subdirectory| fs::read(directory.join(subdirectory).join(term)).ok())
+            .and_then(|entry| parse_terminfo_colors(&entry))
+    })
+}
+
+/// Finds the "colors" number in a compiled terminfo entry, as described in term(5).
+fn parse_terminfo_colors(entry: &[u8]) -> Option<u32> {
+    /// Position of "colors" among the numbers of an entry.
+    const COLORS: usize = 13;
+
+    let short = |index: usize| {
+        entry
+            .get(index * 2..index * 2 + 2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    // Numbers are 16 bits in the legacy format, and 32 bits in the extended one.
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names_size = usize::try_from(short(1)?).ok()?;
+    let bools_count = usize::try_from(short(2)?).ok()?;
+    let numbers_count = usize::try_from(short(3)?).ok()?;
+    if COLORS >= numbers_count {
+        return None;
+    }
+
+    // The numbers are aligned to an even offset.
+    let mut numbers_start = 12 + names_size + bools_count;
+    numbers_start += numbers_start % 2;
+    let start = numbers_start + COLORS * number_size;
+    let colors = match number_size {
+        2 => i32::from(i16::from_le_bytes(
+            entry.get(start..start + 2)?.try_into().ok()?,
+        )),
+        _ => i32::from_le_bytes(entry.get(start..start + 4)?.try_into().ok()?),
+    };
+    // Missing numbers are negative.
+    u32::try_from(colors).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate serde_yaml;
+
+    #[test]
+    fn it_parses_colors() {
+        assert_eq!(serde_yaml::from_str::<Color>("red").unwrap(), Color::Red);
+        assert_eq!(
+            serde_yaml::from_str::<Color>("bright_magenta").unwrap(),
+            Color::BrightMagenta
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Color>(r##""#FF8800""##).unwrap(),
+            Color::Rgb(255, 136, 0)
+        );
+        assert_eq!(
+            serde_yaml::from_str::<Color>("208").unwrap(),
+            Color::Indexed(208)
+        );
+
+        assert!(serde_yaml::from_str::<Color>("purple").is_err());
+        assert!(serde_yaml::from_str::<Color>(r##""#ff88""##).is_err());
+        assert!(serde_yaml::from_str::<Color>("256").is_err());
+        assert!(serde_yaml::from_str::<Color>("-1").is_err());
+    }
+
+    #[test]
+    fn it_formats_colors_like_they_are_parsed() {
+        for color in &[
+            Color::Reset,
+            Color::BrightYellow,
+            Color::Indexed(3),
+            Color::Rgb(1, 2, 255),
+        ] {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), *color);
+        }
+    }
+
+    #[test]
+    fn it_degrades_colors() {
+        let orange = Color::Rgb(255, 136, 0);
+        assert_eq!(
+            orange.to_tui(ColorSupport::TrueColor),
+            TuiColor::Rgb(255, 136, 0)
+        );
+        assert_eq!(orange.to_tui(ColorSupport::Basic), TuiColor::Yellow);
+        assert_eq!(orange.to_tui(ColorSupport::NoColor), TuiColor::Reset);
+
+        assert_eq!(
+            Color::Indexed(208).to_tui(ColorSupport::TrueColor),
+            TuiColor::Rgb(255, 135, 0)
+        );
+        assert_eq!(
+            Color::Indexed(244).to_tui(ColorSupport::TrueColor),
+            TuiColor::Rgb(128, 128, 128)
+        );
+        assert_eq!(
+            Color::Indexed(9).to_tui(ColorSupport::TrueColor),
+            TuiColor::LightRed
+        );
+        assert_eq!(Color::Red.to_tui(ColorSupport::NoColor), TuiColor::Reset);
+
+        assert_eq!(
+            Color::Indexed(208).to_tui(ColorSupport::Palette),
+            TuiColor::Rgb(255, 135, 0)
+        );
+        assert_eq!(
+            orange.to_tui(ColorSupport::Palette),
+            TuiColor::Rgb(255, 135, 0)
+        );
+        assert_eq!(
+            Color::Indexed(3).to_tui(ColorSupport::Palette),
+            TuiColor::Yellow
+        );
+    }
+
+    #[test]
+    fn it_finds_palette_indexes() {
+        for index in 16..=255 {
+            assert_eq!(palette_index(palette_rgb(index)), index);
+        }
+        assert_eq!(palette_index((250, 130, 10)), 208);
+        assert_eq!(palette_index((0, 0, 0)), 16);
+    }
+
+    #[test]
+    fn it_reads_colors_from_terminfo() {
+        // Header, names, one boolean, padding, and 14 numbers in the legacy format.
+        let mut entry = Vec::new();
+        for short in &[0o432, 6, 1, 14, 0, 0] {
+            entry.extend_from_slice(&i16::to_le_bytes(*short));
+        }
+        entry.extend_from_slice(b"term|\0");
+        entry.extend_from_slice(&[1, 0]);
+        for number in 0..14 {
+            let value: i16 = if number == 13 { 256 } else { -1 };
+            entry.extend_from_slice(&value.to_le_bytes());
+        }
+        assert_eq!(parse_terminfo_colors(&entry), Some(256));
+
+        entry[0] = 0;
+        assert_eq!(parse_terminfo_colors(&entry), None);
+    }
+
+    #[test]
+    fn it_detects_color_support() {
+        let detect = |no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>| {
+            ColorSupport::detect(
+                no_color.map(String::from),
+                colorterm.map(String::from),
+                term.map(String::from),
+                None,
+            )
+        };
+
+        assert_eq!(
+            detect(None, Some("truecolor"), Some("xterm-256color")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("xterm-256color")),
+            ColorSupport::Palette
+        );
+        assert_eq!(
+            detect(None, None, Some("screen.xterm-256color")),
+            ColorSupport::Palette
+        );
+        assert_eq!(detect(None, None, Some("xterm")), ColorSupport::Basic);
+        assert_eq!(
+            detect(Some("1"), None, Some("xterm-256color")),
+            ColorSupport::NoColor
+        );
+        assert_eq!(
+            ColorSupport::detect(None, None, Some(String::from("foot")), Some(256)),
+            ColorSupport::Palette
+        );
+        assert_eq!(
+            ColorSupport::detect(None, None, Some(String::from("vt220")), Some(8)),
+            ColorSupport::Basic
+        );
+        assert_eq!(
+            detect(Some("1"), Some("truecolor"), Some("xterm")),
+            ColorSupport::NoColor
+        );
+        assert_eq!(detect(Some(""), None, Some("xterm")), ColorSupport::Basic);
+        assert_eq!(detect(None, None, Some("dumb")), ColorSupport::NoColor);
+    }
+
+    #[test]
+    fn it_merges_styles() {
+        let base = TextStyle {
+            fg: Some(Color::Red),
+            bg: Some(Color::Black),
+            attribute: None,
+        };
+        let other = TextStyle {
+            fg: Some(Color::Blue),
+            bg: None,
+            attribute: Some(Attribute::Bold),
+        };
+
+        assert_eq!(
+            base.merge(&other),
+            TextStyle {
+                fg: Some(Color::Blue),
+                bg: Some(Color::Black),
+                attribute: Some(Attribute::Bold),
+            }
+        );
+    }
+
+    #[test]
+    fn it_creates_markup() {
+        assert_eq!(TextStyle::default().markup("Hello"), "Hello");
+        assert_eq!(
+            TextStyle {
+                fg: Some(Color::Rgb(255, 0, 0)),
+                bg: Some(Color::Indexed(17)),
+                attribute: Some(Attribute::Italic),
+            }
+            .markup("Hello"),
+            "{fg=#ff0000;bg=17;mod=italic Hello}"
+        );
+    }
+}
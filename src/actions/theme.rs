@@ -0,0 +1,341 @@
+use super::style::TextStyle;
+use failure::{Error, ResultExt};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, SubschemaValidation};
+use schemars::JsonSchema;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Themes that are built into tydra, by name.
+const BUNDLED_THEMES: [(&str, &str); 3] = [
+    ("default", include_str!("../../themes/default.yml")),
+    ("solarized", include_str!("../../themes/solarized.yml")),
+    (
+        "high-contrast",
+        include_str!("../../themes/high-contrast.yml"),
+    ),
+];
+
+/// Themes can extend other themes, but not forever.
+const MAX_THEME_DEPTH: usize = 8;
+
+const THEME_FIELDS: [&str; 12] = [
+    "extends",
+    "title",
+    "header",
+    "footer",
+    "group_title",
+    "entry_title",
+    "shortcut",
+    "brackets",
+    "separator",
+    "selected",
+    "border",
+    "status_bar",
+];
+
+/// Styles for the different parts of the menu.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// The page title.
+    pub title: TextStyle,
+    /// The page header.
+    pub header: TextStyle,
+    /// The page footer.
+    pub footer: TextStyle,
+    /// Titles of groups.
+    pub group_title: TextStyle,
+    /// Titles of entries.
+    pub entry_title: TextStyle,
+    /// The shortcut key of entries.
+    pub shortcut: TextStyle,
+    /// The brackets around shortcut keys.
+    pub brackets: TextStyle,
+    /// Separators, like the markers around the page title.
+    pub separator: TextStyle,
+    /// The entry that was run last, when coming back to its page.
+    pub selected: TextStyle,
+    /// Borders around boxes.
+    pub border: TextStyle,
+    /// The status bar at the bottom of the screen.
+    pub status_bar: TextStyle,
+}
+
+/// A theme as written in settings; either the name of a theme, or a set of styles that can extend
+/// another theme.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThemeSetting {
+    /// A bundled theme, or the path to a theme file.
+    Named(String),
+    Custom(CustomTheme),
+}
+
+/// A theme that is written out in the action file, or in a theme file.
+// This is deserialized by the ThemeSettingVisitor, as serde cannot reject unknown fields of
+// flattened structs.
+#[derive(Debug, Clone, PartialEq, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTheme {
+    /// Bundled theme or theme file that this theme is based on.
+    pub extends: Option<String>,
+    #[serde(flatten)]
+    pub styles: Theme,
+}
+
+impl Theme {
+    /// The bundled *default* theme, which the settings of every action file start from.
+    pub fn bundled_default() -> Theme {
+        load_theme("default", Path::new(""), 0, &mut Vec::new())
+            .expect("The bundled default theme is broken. Please report this as a bug!")
+    }
+
+    /// Returns a new theme where every style in the other theme is merged into the styles of
+    /// this one.
+    pub fn merge(&self, other: &Theme) -> Theme {
+        Theme {
+            title: self.title.merge(&other.title),
+            header: self.header.merge(&other.header),
+            footer: self.footer.merge(&other.footer),
+            group_title: self.group_title.merge(&other.group_title),
+            entry_title: self.entry_title.merge(&other.entry_title),
+            shortcut: self.shortcut.merge(&other.shortcut),
+            brackets: self.brackets.merge(&other.brackets),
+            separator: self.separator.merge(&other.separator),
+            selected: self.selected.merge(&other.selected),
+            border: self.border.merge(&other.border),
+            status_bar: self.status_bar.merge(&other.status_bar),
+        }
+    }
+
+    fn style_mut(&mut self, name: &str) -> Option<&mut TextStyle> {
+        match name {
+            "title" => Some(&mut self.title),
+            "header" => Some(&mut self.header),
+            "footer" => Some(&mut self.footer),
+            "group_title" => Some(&mut self.group_title),
+            "entry_title" => Some(&mut self.entry_title),
+            "shortcut" => Some(&mut self.shortcut),
+            "brackets" => Some(&mut self.brackets),
+            "separator" => Some(&mut self.separator),
+            "selected" => Some(&mut self.selected),
+            "border" => Some(&mut self.border),
+            "status_bar" => Some(&mut self.status_bar),
+            _ => None,
+        }
+    }
+}
+
+impl ThemeSetting {
+    /// Loads all themes that this setting is based on and returns the complete theme.
+    ///
+    /// Names that are not bundled themes are read as paths to theme files, relative to the
+    /// directory given here. Theme files can in turn extend themes relative to their own
+    /// directory. The paths of all theme files that were read are added to `files`.
+    pub fn resolve(&self, directory: &Path, files: &mut Vec<PathBuf>) -> Result<Theme, Error> {
+        match *self {
+            ThemeSetting::Named(ref name) => load_theme(name, directory, 0, files),
+            ThemeSetting::Custom(ref custom) => resolve_custom(custom, directory, 0, files),
+        }
+    }
+
+    /// The styles of this theme, if it does not need anything else to be loaded.
+    pub fn resolved(&self) -> Option<&Theme> {
+        match *self {
+            ThemeSetting::Custom(CustomTheme {
+                extends: None,
+                ref styles,
+            }) => Some(styles),
+            _ => None,
+        }
+    }
+}
+
+impl From<Theme> for ThemeSetting {
+    fn from(styles: Theme) -> ThemeSetting {
+        ThemeSetting::Custom(CustomTheme {
+            extends: None,
+            styles,
+        })
+    }
+}
+
+fn resolve_custom(
+    custom: &CustomTheme,
+    directory: &Path,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+) -> Result<Theme, Error> {
+    let base = match custom.extends {
+        Some(ref name) => load_theme(name, directory, depth + 1, files)?,
+        None => Theme::default(),
+    };
+    Ok(base.merge(&custom.styles))
+}
+
+fn load_theme(
+    name: &str,
+    directory: &Path,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+) -> Result<Theme, Error> {
+    if depth > MAX_THEME_DEPTH {
+        return Err(format_err!(
+            "Theme {} extends too many other themes. Do some of them extend each other?",
+            name
+        ));
+    }
+
+    if let Some((_, source)) = BUNDLED_THEMES.iter().find(|(bundled, _)| *bundled == name) {
+        let custom = parse_theme_file(source)?;
+        return resolve_custom(&custom, directory, depth, files);
+    }
+
+    let path = directory.join(name);
+    let source = std::fs::read_to_string(&path)
+        .with_context(|_| format!("Could not read theme file {}", path.display()))?;
+    let custom = parse_theme_file(&source)
+        .with_context(|_| format!("Could not parse theme file {}", path.display()))?;
+
+    let theme_directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    files.push(path);
+    resolve_custom(&custom, &theme_directory, depth, files)
+}
+
+fn parse_theme_file(source: &str) -> Result<CustomTheme, Error> {
+    match serde_yaml::from_str(source)? {
+        ThemeSetting::Custom(custom) => Ok(custom),
+        ThemeSetting::Named(_) => Err(format_err!("Theme files must contain a map of styles")),
+    }
+}
+
+/// Parse a theme name from a string, or a custom theme from a map.
+struct ThemeSettingVisitor;
+
+impl<'de> Visitor<'de> for ThemeSettingVisitor {
+    type Value = ThemeSetting;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the name of a theme, a path to a theme file, or a map of styles")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<ThemeSetting, E>
+    where
+        E: de::Error,
+    {
+        Ok(ThemeSetting::Named(value.to_owned()))
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<ThemeSetting, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut custom = CustomTheme::default();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "extends" {
+                custom.extends = Some(map.next_value()?);
+            } else if let Some(style) = custom.styles.style_mut(&key) {
+                *style = map.next_value()?;
+            } else {
+                return Err(de::Error::unknown_field(&key, &THEME_FIELDS));
+            }
+        }
+        Ok(ThemeSetting::Custom(custom))
+    }
+}
+
+impl<'de> Deserialize<'de> for ThemeSetting {
+    fn deserialize<D>(deserializer: D) -> Result<ThemeSetting, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ThemeSettingVisitor)
+    }
+}
+
+impl JsonSchema for ThemeSetting {
+    fn schema_name() -> String {
+        String::from("ThemeSetting")
+    }
+
+    /// Mirrors the ThemeSettingVisitor: a theme name or path, or a custom theme.
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let name = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        };
+
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![name.into(), gen.subschema_for::<CustomTheme>()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::style::{Attribute, Color};
+    use crate::testing::TestDirectory;
+
+    #[test]
+    fn it_loads_bundled_themes() {
+        for (name, _) in BUNDLED_THEMES.iter() {
+            ThemeSetting::Named((*name).to_owned())
+                .resolve(Path::new("."), &mut Vec::new())
+                .unwrap();
+        }
+
+        let theme = ThemeSetting::Named(String::from("high-contrast"))
+            .resolve(Path::new("."), &mut Vec::new())
+            .unwrap();
+        assert_eq!(theme.selected.attribute, Some(Attribute::Invert));
+    }
+
+    #[test]
+    fn it_highlights_the_selected_entry_in_the_default_theme() {
+        let theme = Theme::bundled_default();
+        assert_eq!(theme.selected.attribute, Some(Attribute::Bold));
+        assert_eq!(theme.shortcut.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn it_loads_theme_files_that_extend_other_themes() {
+        let directory = TestDirectory::new("theme-files");
+        std::fs::create_dir_all(directory.join("themes")).unwrap();
+        std::fs::write(
+            directory.join("themes/base.yml"),
+            "extends: solarized\ntitle: {fg: red}\n",
+        )
+        .unwrap();
+
+        let setting: ThemeSetting =
+            serde_yaml::from_str("extends: themes/base.yml\nfooter: {attribute: dim}").unwrap();
+        let mut files = Vec::new();
+        let theme = setting.resolve(directory.path(), &mut files).unwrap();
+        assert_eq!(files, [directory.join("themes/base.yml")]);
+
+        assert_eq!(theme.title.fg, Some(Color::Red));
+        assert_eq!(theme.title.attribute, Some(Attribute::Bold));
+        assert_eq!(theme.shortcut.fg, Some(Color::Rgb(0xcb, 0x4b, 0x16)));
+        assert_eq!(theme.footer.attribute, Some(Attribute::Dim));
+    }
+
+    #[test]
+    fn it_fails_on_broken_themes() {
+        let directory = TestDirectory::new("broken-themes");
+        std::fs::write(directory.join("loop.yml"), "extends: loop.yml\n").unwrap();
+
+        for setting in &["missing.yml", "loop.yml"] {
+            assert!(ThemeSetting::Named((*setting).to_owned())
+                .resolve(directory.path(), &mut Vec::new())
+                .is_err());
+        }
+        assert!(serde_yaml::from_str::<ThemeSetting>("titel: {fg: red}").is_err());
+    }
+}
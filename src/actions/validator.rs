@@ -1,25 +1,23 @@
 use crate::actions::{ActionFile, Command, Entry, Return, RunMode};
+use crate::recent::is_virtual_page;
 use std::collections::HashSet;
 
 #[derive(Debug, PartialEq, Fail)]
 pub enum ValidationError {
-    #[fail(
-        display = "Found reference to an unknown page: {}",
-        page_name
-    )]
+    #[fail(display = "Found reference to an unknown page: {}", page_name)]
     UnknownPage { page_name: String },
     #[fail(display = "Found page with no entries: {}", page_name)]
     EmptyPage { page_name: String },
     #[fail(
-        display = "Specified root page does not exist: {}",
-        root_name
+        display = "Page names starting with @ are reserved for built-in pages: {}",
+        page_name
     )]
+    ReservedPageName { page_name: String },
+    #[fail(display = "Specified root page does not exist: {}", root_name)]
     NoRoot { root_name: String },
     #[fail(
         display = "Page {} has a duplicated shortcut: {} ({})",
-        page_name,
-        shortcut,
-        title
+        page_name, shortcut, title
     )]
     DuplicatedShortcut {
         page_name: String,
@@ -28,30 +26,59 @@ pub enum ValidationError {
     },
     #[fail(
         display = "Entry cannot return and exec at the same time; exec will replace tydra process (page {}, shortcut {}).",
-        page_name,
-        shortcut
+        page_name, shortcut
     )]
     ExecWithReturn { page_name: String, shortcut: char },
     #[fail(
         display = "Entry cannot exec without a command (page {}, shortcut {}).",
-        page_name,
-        shortcut
+        page_name, shortcut
     )]
     ExecWithoutCommand { page_name: String, shortcut: char },
+    #[fail(
+        display = "Entry cannot return and type at the same time; typing exits tydra (page {}, shortcut {}).",
+        page_name, shortcut
+    )]
+    TypeWithReturn { page_name: String, shortcut: char },
+    #[fail(
+        display = "Entry has no value or command to {} (page {}, shortcut {}).",
+        mode, page_name, shortcut
+    )]
+    NothingToCopy {
+        mode: RunMode,
+        page_name: String,
+        shortcut: char,
+    },
+    #[fail(
+        display = "Entry id {} is used more than once (page {}, shortcut {}).",
+        id, page_name, shortcut
+    )]
+    DuplicatedId {
+        id: String,
+        page_name: String,
+        shortcut: char,
+    },
 }
 
 pub fn validate(actions: &ActionFile, root_name: &str) -> Result<(), Vec<ValidationError>> {
     let mut errors: Vec<ValidationError> = Vec::new();
 
-    if !actions.has_page(root_name) {
+    if !actions.has_page(root_name) && !is_virtual_page(root_name) {
         errors.push(ValidationError::NoRoot {
             root_name: root_name.into(),
         });
     }
 
+    let mut seen_ids = HashSet::new();
+
     for (page, page_name) in actions.pages_with_names() {
         let mut seen_shortcuts = HashSet::new();
 
+        if page_name.starts_with('@') {
+            errors.push(ValidationError::ReservedPageName {
+                page_name: page_name.to_owned(),
+            });
+        }
+
         if page.all_entries().next().is_none() {
             errors.push(ValidationError::EmptyPage {
                 page_name: page_name.to_owned(),
@@ -60,6 +87,7 @@ pub fn validate(actions: &ActionFile, root_name: &str) -> Result<(), Vec<Validat
 
         for entry in page.all_entries() {
             validate_shortcut_duplicates(&mut errors, entry, &mut seen_shortcuts, page_name);
+            validate_id_duplicates(&mut errors, entry, &mut seen_ids, page_name);
             validate_return_link(&mut errors, entry, actions);
             validate_mode(&mut errors, entry, page_name);
         }
@@ -88,9 +116,26 @@ fn validate_shortcut_duplicates(
     }
 }
 
+fn validate_id_duplicates<'a>(
+    errors: &mut Vec<ValidationError>,
+    entry: &'a Entry,
+    seen_ids: &mut HashSet<&'a str>,
+    page_name: &str,
+) {
+    if let Some(id) = entry.id() {
+        if !seen_ids.insert(id) {
+            errors.push(ValidationError::DuplicatedId {
+                id: id.to_owned(),
+                page_name: page_name.to_owned(),
+                shortcut: entry.shortcut(),
+            });
+        }
+    }
+}
+
 fn validate_return_link(errors: &mut Vec<ValidationError>, entry: &Entry, actions: &ActionFile) {
     if let Return::OtherPage(page_name) = entry.return_to() {
-        if !actions.has_page(page_name) {
+        if !actions.has_page(page_name) && !is_virtual_page(page_name) {
             errors.push(ValidationError::UnknownPage {
                 page_name: page_name.clone(),
             });
@@ -109,14 +154,28 @@ fn validate_mode(errors: &mut Vec<ValidationError>, entry: &Entry, page_name: &s
             }
             Return::Quit => {}
         }
-        match entry.command() {
-            Command::None => errors.push(ValidationError::ExecWithoutCommand {
+        if entry.command() == &Command::None {
+            errors.push(ValidationError::ExecWithoutCommand {
                 page_name: page_name.to_owned(),
                 shortcut: entry.shortcut(),
-            }),
-            _ => {}
+            });
         }
     }
+
+    let mode = entry.runner_mode();
+    if mode == RunMode::Type && *entry.return_to() != Return::Quit {
+        errors.push(ValidationError::TypeWithReturn {
+            page_name: page_name.to_owned(),
+            shortcut: entry.shortcut(),
+        });
+    }
+    if (mode == RunMode::Copy || mode == RunMode::Type) && entry.snippet().is_none() {
+        errors.push(ValidationError::NothingToCopy {
+            mode,
+            page_name: page_name.to_owned(),
+            shortcut: entry.shortcut(),
+        });
+    }
 }
 
 #[cfg(test)]
@@ -158,7 +217,8 @@ pages:
   this_page_is_empty:
     groups:
         - entries: []"#,
-        ).unwrap();
+        )
+        .unwrap();
 
         let errors = validate(&actions, "root").unwrap_err();
 
@@ -181,7 +241,8 @@ pages:
       - entries:
           - shortcut: a
             title: Working"#,
-        ).unwrap();
+        )
+        .unwrap();
 
         let errors = validate(&actions, "horseradish").unwrap_err();
 
@@ -194,6 +255,38 @@ pages:
         );
     }
 
+    #[test]
+    fn it_validates_pages_made_from_the_history() {
+        let actions: ActionFile = serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    groups:
+      - entries:
+          - {shortcut: r, title: Recent, return: "@recent"}
+          - {shortcut: f, title: Frequent, return: "@frequent"}
+          - {shortcut: o, title: Other, return: "@other"}
+  "@mine":
+    groups:
+      - entries:
+          - {shortcut: a, title: Working}"#,
+        )
+        .unwrap();
+
+        let errors = validate(&actions, "@recent").unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::ReservedPageName {
+                    page_name: String::from("@mine"),
+                },
+                ValidationError::UnknownPage {
+                    page_name: String::from("@other"),
+                },
+            ]
+        );
+    }
+
     #[test]
     fn it_validates_duplicated_keys() {
         let actions: ActionFile = serde_yaml::from_str(
@@ -215,7 +308,8 @@ pages:
       - entries:
           - shortcut: a
             title: Duplicated shortcut"#,
-        ).unwrap();
+        )
+        .unwrap();
 
         let errors = validate(&actions, "root").unwrap_err();
 
@@ -230,6 +324,42 @@ pages:
         );
     }
 
+    #[test]
+    fn it_validates_duplicated_ids() {
+        let actions: ActionFile = serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    groups:
+      - entries:
+          - shortcut: a
+            id: unique
+            title: This is fine
+          - shortcut: b
+            id: deploy
+            title: First one
+  other:
+    groups:
+      - entries:
+          - shortcut: a
+            id: deploy
+            title: Same id on another page"#,
+        )
+        .unwrap();
+
+        let errors = validate(&actions, "root").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0],
+            ValidationError::DuplicatedId {
+                id: "deploy".into(),
+                page_name: "root".into(),
+                shortcut: 'b',
+            }
+        );
+    }
+
     #[test]
     fn it_validates_no_exec_with_return() {
         let actions: ActionFile = serde_yaml::from_str(
@@ -252,7 +382,8 @@ pages:
             command: /bin/true
             mode: exec
             return: root"#,
-        ).unwrap();
+        )
+        .unwrap();
 
         let errors = validate(&actions, "root").unwrap_err();
 
@@ -292,7 +423,8 @@ pages:
           - shortcut: c
             title: This neither (no command mentioned)
             mode: exec"#,
-        ).unwrap();
+        )
+        .unwrap();
 
         let errors = validate(&actions, "root").unwrap_err();
 
@@ -312,4 +444,37 @@ pages:
             }
         );
     }
+
+    #[test]
+    fn it_validates_copy_and_type_entries() {
+        let actions: ActionFile = serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    groups:
+      - entries:
+          - {shortcut: a, title: Value, mode: copy, value: "https://example.com"}
+          - {shortcut: b, title: Command, mode: type, command: "git status"}
+          - {shortcut: c, title: Nothing, mode: copy}
+          - {shortcut: d, title: Returning, mode: type, value: "ls", return: true}"#,
+        )
+        .unwrap();
+
+        let errors = validate(&actions, "root").unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::NothingToCopy {
+                    mode: RunMode::Copy,
+                    page_name: "root".into(),
+                    shortcut: 'c',
+                },
+                ValidationError::TypeWithReturn {
+                    page_name: "root".into(),
+                    shortcut: 'd',
+                },
+            ]
+        );
+    }
 }
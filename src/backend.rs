@@ -0,0 +1,366 @@
+use crate::actions::{palette_index, ColorSupport};
+use nix::poll::{poll, PollFd, PollFlags};
+use std::io::{self, Write};
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use tui::backend::Backend;
+#[cfg(test)]
+use tui::buffer::Buffer;
+use tui::buffer::Cell;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+
+/// How long to wait for the terminal to report the cursor position.
+const CURSOR_POSITION_TIMEOUT_MS: i32 = 1000;
+
+/// Which part of the terminal the menu is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    /// The alternate screen, covering the whole terminal.
+    Alternate,
+    /// A number of lines below the cursor, on the normal screen.
+    Inline { height: u16 },
+}
+
+/// Termion backend that draws on the alternate screen, or inline in a region of the normal
+/// screen.
+///
+/// This works like the `AlternateScreenBackend` in tui, except for how text attributes are
+/// switched. Terminals can show several attributes at once, so switching from bold to italic
+/// must turn off bold first. tui only does that when switching back to no attribute at all, which
+/// makes one attribute leak into the next piece of styled text.
+///
+/// tui has no palette colors, so terminals with only the 256-color palette get the palette index
+/// of each RGB color instead of the RGB value itself.
+pub struct ScreenBackend {
+    stdout: Box<dyn Write>,
+    region: Option<Region>,
+    support: ColorSupport,
+}
+
+/// Lines of the normal screen that an inline menu is drawn on. Rows and columns start at 1, like
+/// in the terminal's cursor movements.
+struct Region {
+    top: u16,
+    height: u16,
+    /// Where the cursor was before the region was set up, to put it back there afterwards.
+    cursor: (u16, u16),
+}
+
+impl ScreenBackend {
+    pub fn new(screen: Screen) -> io::Result<ScreenBackend> {
+        let raw = io::stdout().into_raw_mode()?;
+        let support = ColorSupport::current();
+        match screen {
+            Screen::Alternate => Ok(ScreenBackend {
+                stdout: Box::new(AlternateScreen::from(raw)),
+                region: None,
+                support,
+            }),
+            Screen::Inline { height } => {
+                let mut stdout: Box<dyn Write> = Box::new(raw);
+                let region = reserve_region(&mut stdout, height)?;
+                Ok(ScreenBackend {
+                    stdout,
+                    region: Some(region),
+                    support,
+                })
+            }
+        }
+    }
+
+    fn fg(&self, color: Color) -> String {
+        match (color, self.support) {
+            (Color::Rgb(r, g, b), ColorSupport::Palette) => {
+                termion::color::Fg(termion::color::AnsiValue(palette_index((r, g, b)))).to_string()
+            }
+            _ => color.termion_fg(),
+        }
+    }
+
+    fn bg(&self, color: Color) -> String {
+        match (color, self.support) {
+            (Color::Rgb(r, g, b), ColorSupport::Palette) => {
+                termion::color::Bg(termion::color::AnsiValue(palette_index((r, g, b)))).to_string()
+            }
+            _ => color.termion_bg(),
+        }
+    }
+
+    /// Moves the cursor to a position in the menu, where 0, 0 is the top left corner.
+    fn goto(&self, x: u16, y: u16) -> termion::cursor::Goto {
+        let top = self.region.as_ref().map_or(1, |region| region.top);
+        termion::cursor::Goto(x + 1, y + top)
+    }
+
+    fn clear_region(&mut self, region_top: u16, height: u16) -> io::Result<()> {
+        for row in region_top..region_top + height {
+            write!(
+                self.stdout,
+                "{}{}",
+                termion::cursor::Goto(1, row),
+                termion::clear::CurrentLine
+            )?;
+        }
+        write!(self.stdout, "{}", termion::cursor::Goto(1, region_top))
+    }
+}
+
+/// Makes room for the menu below the cursor, scrolling the screen up if there are not enough
+/// lines left. The menu starts on the line of the cursor when it is at the start of a line, so
+/// that running tydra from a shell prompt does not leave an empty line, and on the next line
+/// otherwise.
+fn reserve_region(stdout: &mut Box<dyn Write>, height: u16) -> io::Result<Region> {
+    let (_, screen_height) = termion::terminal_size()?;
+    let height = height.clamp(1, screen_height);
+    let (column, mut row) = cursor_position(stdout)?;
+
+    let mut top = if column > 1 { row + 1 } else { row };
+    let bottom = top + height - 1;
+    if bottom > screen_height {
+        let scroll = bottom - screen_height;
+        write!(
+            stdout,
+            "{}{}",
+            termion::cursor::Goto(1, screen_height),
+            "\n".repeat(scroll as usize)
+        )?;
+        top -= scroll;
+        row = row.saturating_sub(scroll).max(1);
+    }
+
+    Ok(Region {
+        top,
+        height,
+        cursor: (column, row),
+    })
+}
+
+/// Asks the terminal where the cursor is, and reads the answer from stdin.
+///
+/// termion can do this too, but it starts a thread that keeps reading stdin afterwards, which
+/// would swallow key presses meant for the menu.
+fn cursor_position(stdout: &mut Box<dyn Write>) -> io::Result<(u16, u16)> {
+    write!(stdout, "\x1b[6n")?;
+    stdout.flush()?;
+
+    let mut response = Vec::new();
+    loop {
+        let mut fds = [PollFd::new(0, PollFlags::POLLIN)];
+        let ready = poll(&mut fds, CURSOR_POSITION_TIMEOUT_MS).map_err(io::Error::other)?;
+        if ready == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Terminal did not report the cursor position",
+            ));
+        }
+
+        let mut byte = [0];
+        if nix::unistd::read(0, &mut byte).map_err(io::Error::other)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        response.push(byte[0]);
+        if byte[0] == b'R' {
+            break;
+        }
+    }
+
+    parse_cursor_position(&response).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Terminal reported the cursor position in an unknown format",
+        )
+    })
+}
+
+/// Parses a cursor position report like `ESC [ row ; column R`, returning the column and row.
+fn parse_cursor_position(response: &[u8]) -> Option<(u16, u16)> {
+    let response = std::str::from_utf8(response).ok()?;
+    // Anything before the report was typed by the user, and is skipped.
+    let start = response.rfind("\x1b[")?;
+    let position = response[start + 2..].strip_suffix('R')?;
+    let mut parts = position.splitn(2, ';');
+    let row = parts.next()?.parse().ok()?;
+    let column = parts.next()?.parse().ok()?;
+    Some((column, row))
+}
+
+impl Drop for ScreenBackend {
+    /// Erases an inline menu, and puts the cursor back where it was.
+    fn drop(&mut self) {
+        if let Some(Region {
+            top,
+            height,
+            cursor,
+        }) = self.region.take()
+        {
+            self.clear_region(top, height).ok();
+            write!(self.stdout, "{}", termion::cursor::Goto(cursor.0, cursor.1)).ok();
+            self.stdout.flush().ok();
+        }
+    }
+}
+
+impl Backend for ScreenBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let mut string = String::new();
+        let mut style = Style::default();
+        let mut last_position: Option<(u16, u16)> = None;
+
+        for (x, y, cell) in content {
+            if last_position != Some((x.wrapping_sub(1), y)) {
+                string.push_str(&self.goto(x, y).to_string());
+            }
+            last_position = Some((x, y));
+
+            if cell.style.modifier != style.modifier {
+                // Resetting also resets colors, so they need to be set again afterwards.
+                string.push_str(&Modifier::Reset.termion_modifier());
+                style = Style::default();
+                if cell.style.modifier != Modifier::Reset {
+                    string.push_str(&cell.style.modifier.termion_modifier());
+                    style.modifier = cell.style.modifier;
+                }
+            }
+            if cell.style.fg != style.fg {
+                string.push_str(&self.fg(cell.style.fg));
+                style.fg = cell.style.fg;
+            }
+            if cell.style.bg != style.bg {
+                string.push_str(&self.bg(cell.style.bg));
+                style.bg = cell.style.bg;
+            }
+            string.push_str(&cell.symbol);
+        }
+
+        write!(
+            self.stdout,
+            "{}{}{}{}",
+            string,
+            Color::Reset.termion_fg(),
+            Color::Reset.termion_bg(),
+            Modifier::Reset.termion_modifier()
+        )
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        write!(self.stdout, "{}", termion::cursor::Hide)?;
+        self.stdout.flush()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        write!(self.stdout, "{}", termion::cursor::Show)?;
+        self.stdout.flush()
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        match self.region {
+            Some(Region { top, height, .. }) => self.clear_region(top, height)?,
+            None => write!(
+                self.stdout,
+                "{}{}",
+                termion::clear::All,
+                termion::cursor::Goto(1, 1)
+            )?,
+        }
+        self.stdout.flush()
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        let (width, height) = termion::terminal_size()?;
+        Ok(Rect {
+            x: 0,
+            y: 0,
+            width,
+            height: self.region.as_ref().map_or(height, |region| region.height),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+}
+
+/// Backend that draws into a buffer in memory instead of a terminal, so that tests can look at
+/// what would be shown on the screen.
+#[cfg(test)]
+pub struct HeadlessBackend {
+    buffer: Buffer,
+}
+
+#[cfg(test)]
+impl HeadlessBackend {
+    pub fn new(width: u16, height: u16) -> HeadlessBackend {
+        HeadlessBackend {
+            buffer: Buffer::empty(Rect::new(0, 0, width, height)),
+        }
+    }
+
+    /// The text on the screen, without styles and with the spaces at the end of lines removed.
+    pub fn text(&self) -> String {
+        let area = self.buffer.area();
+        let mut text = String::new();
+        for y in area.top()..area.bottom() {
+            let line: String = (area.left()..area.right())
+                .map(|x| self.buffer.get(x, y).symbol.as_str())
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The cell at the given position, with its style.
+    pub fn cell(&self, x: u16, y: u16) -> &Cell {
+        self.buffer.get(x, y)
+    }
+}
+
+#[cfg(test)]
+impl Backend for HeadlessBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        for (x, y, cell) in content {
+            *self.buffer.get_mut(x, y) = cell.clone();
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.buffer.reset();
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        Ok(*self.buffer.area())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_cursor_position_reports() {
+        assert_eq!(parse_cursor_position(b"\x1b[12;1R"), Some((1, 12)));
+        assert_eq!(parse_cursor_position(b"ab\x1b[3;40R"), Some((40, 3)));
+        assert_eq!(parse_cursor_position(b"\x1b[3R"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::actions::Entry;
+use failure::Error;
+use std::str::FromStr;
+
+/// The entry that closed the menu, and the name of the page it was on.
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub page: String,
+    pub entry: Entry,
+}
+
+/// What to print about the chosen entry with --print-choice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChoiceFormat {
+    Value,
+    Id,
+    Shortcut,
+    Title,
+    Command,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct PrintedChoice<'a> {
+    page: &'a str,
+    shortcut: char,
+    id: Option<&'a str>,
+    title: &'a str,
+    value: &'a str,
+}
+
+impl ChoiceFormat {
+    pub fn variants() -> [&'static str; 6] {
+        ["value", "id", "shortcut", "title", "command", "json"]
+    }
+}
+
+impl FromStr for ChoiceFormat {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<ChoiceFormat, Error> {
+        match string {
+            "value" => Ok(ChoiceFormat::Value),
+            "id" => Ok(ChoiceFormat::Id),
+            "shortcut" => Ok(ChoiceFormat::Shortcut),
+            "title" => Ok(ChoiceFormat::Title),
+            "command" => Ok(ChoiceFormat::Command),
+            "json" => Ok(ChoiceFormat::Json),
+            other => Err(format_err!("Unknown choice format: {}", other)),
+        }
+    }
+}
+
+impl Choice {
+    /// Formats the choice as a single line, without a newline at the end. Entries without an id
+    /// are printed as an empty line in the id format, and entries without a command are printed
+    /// as their value in the command format.
+    pub fn format(&self, format: ChoiceFormat) -> String {
+        let entry = &self.entry;
+        match format {
+            ChoiceFormat::Value => entry.value().to_owned(),
+            ChoiceFormat::Id => entry.id().unwrap_or_default().to_owned(),
+            ChoiceFormat::Shortcut => entry.shortcut().to_string(),
+            ChoiceFormat::Title => entry.title().to_owned(),
+            ChoiceFormat::Command => entry
+                .command_line()
+                .unwrap_or_else(|| entry.value().to_owned()),
+            // The choice only holds strings and simple values, so it cannot fail.
+            ChoiceFormat::Json => serde_json::to_string(&PrintedChoice {
+                page: &self.page,
+                shortcut: entry.shortcut(),
+                id: entry.id(),
+                title: entry.title(),
+                value: entry.value(),
+            })
+            .unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Command;
+
+    #[test]
+    fn it_formats_choices() {
+        let choice = Choice {
+            page: String::from("root"),
+            entry: Entry::new('y', "Yes").with_value("yes"),
+        };
+
+        assert_eq!(choice.format(ChoiceFormat::Value), "yes");
+        assert_eq!(choice.format(ChoiceFormat::Id), "");
+        assert_eq!(choice.format(ChoiceFormat::Shortcut), "y");
+        assert_eq!(choice.format(ChoiceFormat::Title), "Yes");
+        assert_eq!(choice.format(ChoiceFormat::Command), "yes");
+        assert_eq!(
+            choice.format(ChoiceFormat::Json),
+            r#"{"page":"root","shortcut":"y","id":null,"title":"Yes","value":"yes"}"#
+        );
+
+        let choice = Choice {
+            page: String::from("root"),
+            entry: Entry::new('n', "No").with_id("no"),
+        };
+        assert_eq!(choice.format(ChoiceFormat::Value), "No");
+        assert_eq!(choice.format(ChoiceFormat::Id), "no");
+
+        let choice = Choice {
+            page: String::from("root"),
+            entry: Entry::new('l', "List").with_command("ls -l"),
+        };
+        assert_eq!(choice.format(ChoiceFormat::Command), "ls -l");
+
+        let choice = Choice {
+            page: String::from("root"),
+            entry: Entry::new('g', "Grep").with_command(Command::Executable {
+                name: String::from("grep"),
+                args: vec![String::from("-r"), String::from("a b|c"), String::from(".")],
+            }),
+        };
+        assert_eq!(choice.format(ChoiceFormat::Command), "grep -r 'a b|c' .");
+    }
+}
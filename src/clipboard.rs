@@ -0,0 +1,83 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use failure::Error;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Puts the text on the clipboard.
+///
+/// The terminal is asked to do it with an OSC 52 escape sequence when stdout is a terminal, which
+/// also works over SSH. Not every terminal supports that, and there is no way to tell, so
+/// `wl-copy` or `xclip` are used as well when there is a display for them.
+pub fn copy(text: &str) -> Result<(), Error> {
+    let mut copied = false;
+
+    if nix::unistd::isatty(1).unwrap_or(false) {
+        let mut stdout = io::stdout();
+        stdout.write_all(osc52(text).as_bytes())?;
+        stdout.flush()?;
+        copied = true;
+    }
+
+    for program in clipboard_programs() {
+        if copy_with(program, text).is_ok() {
+            copied = true;
+            break;
+        }
+    }
+
+    if copied {
+        Ok(())
+    } else {
+        Err(format_err!(
+            "Could not copy to the clipboard: stdout is not a terminal, and neither wl-copy nor \
+             xclip could be used"
+        ))
+    }
+}
+
+/// The escape sequence that asks the terminal to put the text on the clipboard.
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", BASE64.encode(text))
+}
+
+/// Programs that can put text from stdin on the clipboard, for the displays that are available.
+fn clipboard_programs() -> Vec<&'static [&'static str]> {
+    let has_display = |name| std::env::var_os(name).is_some_and(|display| !display.is_empty());
+    let mut programs: Vec<&'static [&'static str]> = Vec::new();
+    if has_display("WAYLAND_DISPLAY") {
+        programs.push(&["wl-copy"]);
+    }
+    if has_display("DISPLAY") {
+        programs.push(&["xclip", "-selection", "clipboard"]);
+    }
+    programs
+}
+
+fn copy_with(program: &[&str], text: &str) -> Result<(), Error> {
+    // Both programs stay in the background to serve the clipboard, and must not draw over the
+    // menu while doing so.
+    let mut child = Command::new(program[0])
+        .args(&program[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format_err!("{} exited with {}", program[0], status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_encodes_text_for_the_terminal() {
+        assert_eq!(osc52("git log ✓"), "\x1b]52;c;Z2l0IGxvZyDinJM=\x07");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::watcher::Watcher;
+use failure::Error;
+use nix::fcntl::OFlag;
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use std::io::{self, Read};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicI32, Ordering};
+use termion::event::Key;
+use termion::input::{Keys, TermRead};
+
+const STDIN: RawFd = 0;
+const ESC: u8 = 0x1B;
+
+/// Something that happened while waiting for user input.
+#[derive(Debug)]
+pub enum Event {
+    /// The user pressed a key.
+    Key(Key),
+
+    /// One of the watched files changed.
+    FileChanged,
+
+    /// The terminal changed its size.
+    Resized,
+}
+
+/// Waits for events from several sources at once; the keyboard, terminal resizes and an optional
+/// file Watcher.
+pub struct Events {
+    keys: Keys<RawStdin>,
+    resize: ResizeSignal,
+    watcher: Option<Watcher>,
+}
+
+/// Write end of the pipe that the SIGWINCH handler writes to, or -1 before it is set up.
+static RESIZE_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// Turns SIGWINCH signals into something that can be polled, by writing to a pipe whenever the
+/// signal arrives. Signal handlers can do very little safely, but writing to a pipe is one of
+/// those things.
+///
+/// The previous handler is put back and the pipe is closed when dropped, as menus can be run from
+/// programs that use tydra as a library.
+struct ResizeSignal {
+    read_fd: RawFd,
+    write_fd: RawFd,
+    previous_action: SigAction,
+}
+
+/// Unbuffered reader of stdin.
+///
+/// `std::io::Stdin` is buffered, which means that bytes that have already been read from the file
+/// descriptor would not wake up a `poll` call. Reading byte by byte keeps the file descriptor as
+/// the single source of truth.
+pub struct RawStdin;
+
+impl Events {
+    pub fn new(watcher: Option<Watcher>) -> Result<Events, Error> {
+        Ok(Events {
+            keys: RawStdin.keys(),
+            resize: ResizeSignal::new()?,
+            watcher,
+        })
+    }
+
+    /// Replaces the file Watcher, for when the set of files to watch has changed.
+    pub fn set_watcher(&mut self, watcher: Option<Watcher>) {
+        self.watcher = watcher;
+    }
+
+    /// Blocks until the next event happens.
+    pub fn next(&mut self) -> Result<Event, Error> {
+        loop {
+            let mut fds = vec![
+                PollFd::new(STDIN, PollFlags::POLLIN),
+                PollFd::new(self.resize.read_fd, PollFlags::POLLIN),
+            ];
+            if let Some(ref watcher) = self.watcher {
+                fds.push(PollFd::new(watcher.as_raw_fd(), PollFlags::POLLIN));
+            }
+
+            match poll(&mut fds, -1) {
+                Ok(_) => {}
+                Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => continue,
+                Err(error) => return Err(error.into()),
+            }
+
+            // Several resizes in a row only need a single redraw.
+            if is_ready(fds[1]) {
+                self.resize.drain()?;
+                return Ok(Event::Resized);
+            }
+
+            if let (Some(watcher), Some(fd)) = (self.watcher.as_mut(), fds.get(2)) {
+                if is_ready(*fd) && watcher.has_changed()? {
+                    return Ok(Event::FileChanged);
+                }
+            }
+
+            if is_ready(fds[0]) {
+                match self.keys.next() {
+                    Some(Ok(key)) => return Ok(Event::Key(key)),
+                    Some(Err(_)) => {}
+                    None => return Err(format_err!("stdin was closed.")),
+                }
+            }
+        }
+    }
+}
+
+impl ResizeSignal {
+    fn new() -> Result<ResizeSignal, Error> {
+        let (read_fd, write_fd) = nix::unistd::pipe2(OFlag::O_CLOEXEC | OFlag::O_NONBLOCK)?;
+        RESIZE_PIPE.store(write_fd, Ordering::SeqCst);
+
+        let action = SigAction::new(
+            SigHandler::Handler(handle_resize),
+            SaFlags::SA_RESTART,
+            SigSet::empty(),
+        );
+        let previous_action = match unsafe { sigaction(Signal::SIGWINCH, &action) } {
+            Ok(previous_action) => previous_action,
+            Err(error) => {
+                RESIZE_PIPE.store(-1, Ordering::SeqCst);
+                nix::unistd::close(read_fd).ok();
+                nix::unistd::close(write_fd).ok();
+                return Err(error.into());
+            }
+        };
+
+        Ok(ResizeSignal {
+            read_fd,
+            write_fd,
+            previous_action,
+        })
+    }
+
+    /// Reads everything that the signal handler has written so far.
+    fn drain(&self) -> Result<(), Error> {
+        let mut buffer = [0; 64];
+        loop {
+            match nix::unistd::read(self.read_fd, &mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(_) => {}
+                Err(nix::Error::Sys(nix::errno::Errno::EAGAIN)) => return Ok(()),
+                Err(nix::Error::Sys(nix::errno::Errno::EINTR)) => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+impl Drop for ResizeSignal {
+    fn drop(&mut self) {
+        // The handler must be gone before the pipe is closed, or it could write to a file
+        // descriptor that has been reused for something else in the meantime.
+        unsafe { sigaction(Signal::SIGWINCH, &self.previous_action) }.ok();
+        RESIZE_PIPE.store(-1, Ordering::SeqCst);
+        nix::unistd::close(self.read_fd).ok();
+        nix::unistd::close(self.write_fd).ok();
+    }
+}
+
+extern "C" fn handle_resize(_: nix::libc::c_int) {
+    let write_fd = RESIZE_PIPE.load(Ordering::SeqCst);
+    if write_fd >= 0 {
+        // The signal can interrupt code that is about to look at errno, which the write must not
+        // change.
+        let errno = unsafe { *errno_location() };
+        // When the pipe is full there is already a resize waiting to be handled, so the error
+        // can be ignored.
+        let _ = nix::unistd::write(write_fd, &[1]);
+        unsafe { *errno_location() = errno };
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn errno_location() -> *mut nix::libc::c_int {
+    nix::libc::__errno_location()
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+unsafe fn errno_location() -> *mut nix::libc::c_int {
+    nix::libc::__error()
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+unsafe fn errno_location() -> *mut nix::libc::c_int {
+    nix::libc::__errno()
+}
+
+impl Read for RawStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let read = nix::unistd::read(STDIN, &mut buf[..1]).map_err(nix_to_io_error)?;
+
+        // termion tells a single Esc press apart from an escape sequence by asking for two bytes
+        // and seeing if it only gets one. Only ever hand over more than one byte in that case, so
+        // that no key presses are left behind in termion's own buffer.
+        if read == 1 && buf[0] == ESC && buf.len() > 1 && has_pending_input()? {
+            let rest = nix::unistd::read(STDIN, &mut buf[1..2]).map_err(nix_to_io_error)?;
+            return Ok(read + rest);
+        }
+
+        Ok(read)
+    }
+}
+
+fn has_pending_input() -> io::Result<bool> {
+    let mut fds = [PollFd::new(STDIN, PollFlags::POLLIN)];
+    poll(&mut fds, 0).map_err(nix_to_io_error)?;
+    Ok(is_ready(fds[0]))
+}
+
+fn is_ready(fd: PollFd) -> bool {
+    fd.revents()
+        .map(|flags| flags.intersects(PollFlags::POLLIN | PollFlags::POLLHUP))
+        .unwrap_or(false)
+}
+
+fn nix_to_io_error(error: nix::Error) -> io::Error {
+    match error.as_errno() {
+        Some(errno) => io::Error::from(errno),
+        None => io::Error::other(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_restores_the_previous_resize_handler() {
+        let signal = ResizeSignal::new().unwrap();
+        assert_eq!(RESIZE_PIPE.load(Ordering::SeqCst), signal.write_fd);
+        drop(signal);
+        assert_eq!(RESIZE_PIPE.load(Ordering::SeqCst), -1);
+
+        let default = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+        let previous = unsafe { sigaction(Signal::SIGWINCH, &default) }.unwrap();
+        assert_eq!(previous.handler(), SigHandler::SigDfl);
+    }
+}
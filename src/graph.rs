@@ -0,0 +1,404 @@
+use crate::actions::{ActionFile, Return};
+use failure::Error;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats that the navigation graph can be printed in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    pub fn variants() -> [&'static str; 2] {
+        ["dot", "mermaid"]
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<GraphFormat, Error> {
+        match string {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            other => Err(format_err!("Unknown graph format: {}", other)),
+        }
+    }
+}
+
+/// Where an edge in the graph leads.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Target<'a> {
+    Page(usize),
+    /// A page made from the history, like `@recent`, that is not in the action file.
+    VirtualPage(&'a str),
+    SamePage,
+    Quit,
+}
+
+struct Node<'a> {
+    name: &'a str,
+    title: &'a str,
+    reachable: bool,
+    start: bool,
+}
+
+/// All pages in the action file, and the shortcuts that lead between them.
+///
+/// Entries that lead to the same place are merged into a single edge, labeled with all of their
+/// shortcuts.
+struct Graph<'a> {
+    nodes: Vec<Node<'a>>,
+    edges: BTreeMap<(usize, Target<'a>), Vec<char>>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(actions: &'a ActionFile, start_page: &str) -> Graph<'a> {
+        let names: Vec<&str> = actions.pages_with_names().map(|(_, name)| name).collect();
+        let index_of = |name: &str| names.iter().position(|other| *other == name);
+
+        let mut edges: BTreeMap<(usize, Target<'a>), Vec<char>> = BTreeMap::new();
+        for (index, (page, _)) in actions.pages_with_names().enumerate() {
+            for entry in page.all_entries() {
+                let target = match entry.return_to() {
+                    Return::Quit => Target::Quit,
+                    Return::SamePage => Target::SamePage,
+                    Return::OtherPage(name) => match index_of(name) {
+                        Some(other) => Target::Page(other),
+                        // Validations make sure that only pages made from the history are missing.
+                        None => Target::VirtualPage(name),
+                    },
+                };
+                edges
+                    .entry((index, target))
+                    .or_default()
+                    .push(entry.shortcut());
+            }
+        }
+
+        let reachable = reachable_pages(&edges, index_of(start_page));
+        let nodes = actions
+            .pages_with_names()
+            .enumerate()
+            .map(|(index, (page, name))| Node {
+                name,
+                title: page.title(),
+                reachable: reachable.contains(&index),
+                start: name == start_page,
+            })
+            .collect();
+
+        Graph { nodes, edges }
+    }
+
+    fn has_quit_edges(&self) -> bool {
+        self.edges.keys().any(|(_, target)| *target == Target::Quit)
+    }
+
+    /// The names of the pages made from the history that entries lead to, without duplicates.
+    fn virtual_pages(&self) -> Vec<&'a str> {
+        let mut names: Vec<&str> = self
+            .edges
+            .keys()
+            .filter_map(|(_, target)| match *target {
+                Target::VirtualPage(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+fn reachable_pages(
+    edges: &BTreeMap<(usize, Target<'_>), Vec<char>>,
+    start: Option<usize>,
+) -> HashSet<usize> {
+    let mut reachable = HashSet::new();
+    let mut queue: VecDeque<usize> = start.into_iter().collect();
+
+    while let Some(index) = queue.pop_front() {
+        if !reachable.insert(index) {
+            continue;
+        }
+        for (from, target) in edges.keys() {
+            if let (true, Target::Page(to)) = (*from == index, target) {
+                queue.push_back(*to);
+            }
+        }
+    }
+
+    reachable
+}
+
+/// Renders the navigation graph between the pages of the action file. Pages that cannot be
+/// reached from the start page are highlighted.
+pub fn graph(actions: &ActionFile, start_page: &str, format: GraphFormat) -> String {
+    let graph = Graph::new(actions, start_page);
+    match format {
+        GraphFormat::Dot => render_dot(&graph),
+        GraphFormat::Mermaid => render_mermaid(&graph),
+    }
+}
+
+fn render_dot(graph: &Graph) -> String {
+    let mut text = String::new();
+    text.push_str("digraph tydra {\n");
+    text.push_str("    node [shape=box];\n");
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let mut attributes = format!(
+            "label=\"{}\"",
+            dot_escape(&format!("{}\n({})", node.title, node.name))
+        );
+        if node.start {
+            attributes.push_str(", peripheries=2");
+        }
+        if !node.reachable {
+            attributes.push_str(", style=filled, fillcolor=\"#ffcccc\", color=red");
+        }
+        writeln!(text, "    page_{} [{}];", index, attributes).unwrap();
+    }
+
+    for name in graph.virtual_pages() {
+        writeln!(
+            text,
+            "    {} [label=\"{}\", style=dashed];",
+            virtual_node(name),
+            dot_escape(name)
+        )
+        .unwrap();
+    }
+
+    if graph.has_quit_edges() {
+        text.push_str("    quit [label=\"Quit\", shape=doublecircle];\n");
+    }
+
+    for ((from, target), shortcuts) in &graph.edges {
+        let label = dot_escape(&shortcut_label(shortcuts));
+        match *target {
+            Target::Page(to) => {
+                writeln!(
+                    text,
+                    "    page_{} -> page_{} [label=\"{}\"];",
+                    from, to, label
+                )
+            }
+            Target::VirtualPage(name) => writeln!(
+                text,
+                "    page_{} -> {} [label=\"{}\"];",
+                from,
+                virtual_node(name),
+                label
+            ),
+            Target::SamePage => writeln!(
+                text,
+                "    page_{} -> page_{} [label=\"{}\", style=dashed];",
+                from, from, label
+            ),
+            Target::Quit => writeln!(
+                text,
+                "    page_{} -> quit [label=\"{}\", style=dotted];",
+                from, label
+            ),
+        }
+        .unwrap();
+    }
+
+    text.push_str("}\n");
+    text
+}
+
+fn render_mermaid(graph: &Graph) -> String {
+    let mut text = String::new();
+    text.push_str("flowchart LR\n");
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let label = mermaid_escape(&format!("{} ({})", node.title, node.name));
+        if node.start {
+            writeln!(text, "    page_{}[[\"{}\"]]", index, label).unwrap();
+        } else {
+            writeln!(text, "    page_{}[\"{}\"]", index, label).unwrap();
+        }
+    }
+
+    for name in graph.virtual_pages() {
+        writeln!(
+            text,
+            "    {}{{{{\"{}\"}}}}",
+            virtual_node(name),
+            mermaid_escape(name)
+        )
+        .unwrap();
+    }
+
+    if graph.has_quit_edges() {
+        text.push_str("    quit([Quit])\n");
+    }
+
+    for ((from, target), shortcuts) in &graph.edges {
+        let label = mermaid_escape(&shortcut_label(shortcuts));
+        match *target {
+            Target::Page(to) => {
+                writeln!(text, "    page_{} -->|\"{}\"| page_{}", from, label, to)
+            }
+            Target::VirtualPage(name) => writeln!(
+                text,
+                "    page_{} -->|\"{}\"| {}",
+                from,
+                label,
+                virtual_node(name)
+            ),
+            Target::SamePage => {
+                writeln!(text, "    page_{} -.->|\"{}\"| page_{}", from, label, from)
+            }
+            Target::Quit => writeln!(text, "    page_{} -.->|\"{}\"| quit", from, label),
+        }
+        .unwrap();
+    }
+
+    let unreachable: Vec<String> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| !node.reachable)
+        .map(|(index, _)| format!("page_{}", index))
+        .collect();
+    if !unreachable.is_empty() {
+        text.push_str("    classDef unreachable fill:#ffcccc,stroke:#ff0000\n");
+        writeln!(text, "    class {} unreachable", unreachable.join(",")).unwrap();
+    }
+
+    text
+}
+
+/// The id of the node of a page made from the history, which has no index in the action file.
+fn virtual_node(name: &str) -> String {
+    format!("history_{}", name.trim_start_matches('@'))
+}
+
+fn shortcut_label(shortcuts: &[char]) -> String {
+    shortcuts
+        .iter()
+        .map(char::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate serde_yaml;
+
+    fn actions() -> ActionFile {
+        serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    title: Welcome
+    groups:
+      - entries:
+          - shortcut: p
+            title: Packages
+            return: packages
+          - shortcut: r
+            title: Refresh
+            return: true
+          - shortcut: h
+            title: Recently used
+            return: "@recent"
+          - shortcut: q
+            title: Quit
+          - shortcut: '"'
+            title: Also quit
+  packages:
+    groups:
+      - entries:
+          - shortcut: q
+            title: Back
+            return: root
+  orphan:
+    title: Forgotten
+    groups:
+      - entries:
+          - shortcut: p
+            title: Packages
+            return: packages"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_renders_dot() {
+        assert_eq!(
+            graph(&actions(), "root", GraphFormat::Dot),
+            r##"digraph tydra {
+    node [shape=box];
+    page_0 [label="Forgotten\n(orphan)", style=filled, fillcolor="#ffcccc", color=red];
+    page_1 [label="Tydra\n(packages)"];
+    page_2 [label="Welcome\n(root)", peripheries=2];
+    history_recent [label="@recent", style=dashed];
+    quit [label="Quit", shape=doublecircle];
+    page_0 -> page_1 [label="p"];
+    page_1 -> page_2 [label="q"];
+    page_2 -> page_1 [label="p"];
+    page_2 -> history_recent [label="h"];
+    page_2 -> page_2 [label="r", style=dashed];
+    page_2 -> quit [label="q, \"", style=dotted];
+}
+"##
+        );
+    }
+
+    #[test]
+    fn it_renders_mermaid() {
+        assert_eq!(
+            graph(&actions(), "root", GraphFormat::Mermaid),
+            r##"flowchart LR
+    page_0["Forgotten (orphan)"]
+    page_1["Tydra (packages)"]
+    page_2[["Welcome (root)"]]
+    history_recent{{"@recent"}}
+    quit([Quit])
+    page_0 -->|"p"| page_1
+    page_1 -->|"q"| page_2
+    page_2 -->|"p"| page_1
+    page_2 -->|"h"| history_recent
+    page_2 -.->|"r"| page_2
+    page_2 -.->|"q, #quot;"| quit
+    classDef unreachable fill:#ffcccc,stroke:#ff0000
+    class page_0 unreachable
+"##
+        );
+    }
+
+    #[test]
+    fn it_finds_reachable_pages_from_the_start_page() {
+        let actions = actions();
+        let graph = Graph::new(&actions, "orphan");
+        assert!(graph.nodes.iter().all(|node| node.reachable));
+
+        let graph = Graph::new(&actions, "packages");
+        let unreachable: Vec<&str> = graph
+            .nodes
+            .iter()
+            .filter(|node| !node.reachable)
+            .map(|node| node.name)
+            .collect();
+        assert_eq!(unreachable, vec!["orphan"]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::actions::{Action, ActionFile, Entry};
+use crate::clipboard;
+use crate::runner;
+use failure::Error;
+
+/// Finds the entry that a `--run` or `tydra run` target refers to, along with the name of the
+/// page it is on.
+///
+/// Targets can be written as:
+///
+/// * `PAGE:SHORTCUT` or `PAGE:ID` for an entry on a specific page, or with a `/` instead of the
+///   `:`.
+/// * `SHORTCUT` for an entry on the default page.
+/// * `ID` for an entry with that id, on any page.
+///
+/// Ids are looked up before the target is split into a page and an entry, so that ids with a `:`
+/// or `/` in them can be run too.
+pub fn find_entry<'a>(
+    actions: &'a ActionFile,
+    target: &str,
+    default_page: &'a str,
+) -> Result<(&'a Entry, &'a str), Error> {
+    if let Some(shortcut) = single_char(target) {
+        return find_entry_on_page(actions, default_page, &shortcut.to_string());
+    }
+
+    if let Some(found) = actions.entry_with_id(target) {
+        return Ok(found);
+    }
+
+    if let Some(index) = target.find([':', '/']) {
+        let (page_name, entry_name) = (&target[..index], &target[index + 1..]);
+        if !page_name.is_empty() {
+            return find_entry_on_page(actions, page_name, entry_name);
+        }
+    }
+
+    Err(format_err!("Could not find an entry with id {}", target))
+}
+
+fn find_entry_on_page<'a>(
+    actions: &'a ActionFile,
+    page_name: &str,
+    entry_name: &str,
+) -> Result<(&'a Entry, &'a str), Error> {
+    let (page, page_name) = actions
+        .pages_with_names()
+        .find(|(_, name)| *name == page_name)
+        .ok_or_else(|| format_err!("Could not find a page named {}", page_name))?;
+
+    let entry = match single_char(entry_name) {
+        Some(shortcut) => page
+            .entry_with_shortcut(shortcut)
+            .or_else(|| page.entry_with_id(entry_name)),
+        None => page.entry_with_id(entry_name),
+    };
+
+    entry.map(|entry| (entry, page_name)).ok_or_else(|| {
+        format_err!(
+            "Could not find an entry with shortcut or id {} on page {}",
+            entry_name,
+            page_name
+        )
+    })
+}
+
+fn single_char(string: &str) -> Option<char> {
+    let mut chars = string.chars();
+    match (chars.next(), chars.next()) {
+        (Some(chr), None) => Some(chr),
+        _ => None,
+    }
+}
+
+/// Runs the command of an entry without any menu, and returns the exit status that tydra should
+/// exit with.
+///
+/// The runner mode is respected like in the menu, except that there is nobody to confirm a
+/// *wait* prompt and nowhere to return to.
+pub fn run_entry(entry: &Entry) -> Result<i32, Error> {
+    match Action::from(entry) {
+        Action::Run { command, .. } => match runner::run_normal(&command) {
+            Some(Ok(exit_status)) => Ok(exit_status.code().unwrap_or(1)),
+            Some(Err(error)) => Err(error),
+            None => Ok(0),
+        },
+        Action::RunExec { command } => Err(runner::run_exec(&command)),
+        Action::RunBackground { command, .. } => unsafe {
+            runner::run_background(&command)?;
+            Ok(0)
+        },
+        Action::Copy { text, .. } => clipboard::copy(&text).map(|()| 0),
+        Action::Type { text } => {
+            println!("{}", text);
+            Ok(0)
+        }
+        action => unreachable!("Entries cannot be converted into {:?}", action),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate serde_yaml;
+
+    fn actions() -> ActionFile {
+        serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    groups:
+      - entries:
+          - shortcut: a
+            title: Root A
+          - shortcut: ":"
+            title: Colon
+  deploy:
+    groups:
+      - entries:
+          - shortcut: a
+            title: Deploy A
+          - shortcut: p
+            id: production
+            title: Production
+          - shortcut: e
+            id: deploy:eu/west
+            title: Europe"#,
+        )
+        .unwrap()
+    }
+
+    fn title_of(target: &str) -> String {
+        let actions = actions();
+        let (entry, page_name) = find_entry(&actions, target, "root").unwrap();
+        format!("{}/{}", page_name, entry.title())
+    }
+
+    #[test]
+    fn it_finds_entries_by_shortcut() {
+        assert_eq!(title_of("a"), "root/Root A");
+        assert_eq!(title_of(":"), "root/Colon");
+        assert_eq!(title_of("root::"), "root/Colon");
+        assert_eq!(title_of("deploy:a"), "deploy/Deploy A");
+        assert_eq!(title_of("deploy/a"), "deploy/Deploy A");
+        assert_eq!(title_of("root/:"), "root/Colon");
+    }
+
+    #[test]
+    fn it_finds_entries_by_id() {
+        assert_eq!(title_of("production"), "deploy/Production");
+        assert_eq!(title_of("deploy:production"), "deploy/Production");
+        assert_eq!(title_of("deploy/production"), "deploy/Production");
+        assert_eq!(title_of("deploy:eu/west"), "deploy/Europe");
+        assert_eq!(title_of("deploy/deploy:eu/west"), "deploy/Europe");
+    }
+
+    #[test]
+    fn it_fails_on_unknown_targets() {
+        let actions = actions();
+        assert!(find_entry(&actions, "b", "root").is_err());
+        assert!(find_entry(&actions, "root:production", "root").is_err());
+        assert!(find_entry(&actions, "nope:a", "root").is_err());
+        assert!(find_entry(&actions, "staging", "root").is_err());
+    }
+}
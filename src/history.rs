@@ -0,0 +1,462 @@
+use crate::actions::{ActionFile, Entry, Page, RunMode};
+use crate::status::RunResult;
+use failure::Error;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Runs that are kept in the history file. Older runs are removed when the menu is opened.
+const MAX_RUNS: usize = 10_000;
+
+/// Entries that have been run from the menu of an action file, stored in a file shared by all
+/// action files.
+pub struct History {
+    path: PathBuf,
+    action_file: String,
+    /// Runs of entries in the action file, oldest first.
+    runs: Vec<Run>,
+}
+
+/// A single run of an entry. Entries are recognized by their id when they have one, and by
+/// their page and shortcut otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    pub action_file: String,
+    pub page: String,
+    pub shortcut: char,
+    pub id: Option<String>,
+    pub title: String,
+    pub mode: RunMode,
+    /// Seconds since the Unix epoch.
+    pub started: u64,
+    /// Commands that tydra did not wait for have no exit status or duration.
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub duration_ms: Option<u64>,
+}
+
+impl History {
+    /// The history file in `$XDG_STATE_HOME/tydra`, which defaults to `~/.local/state/tydra`.
+    pub fn default_path() -> Option<PathBuf> {
+        let state_home = std::env::var_os("XDG_STATE_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/state"))
+            })?;
+        Some(state_home.join("tydra").join("history.jsonl"))
+    }
+
+    /// Reads the history of the action file from the history file, which does not have to exist
+    /// yet.
+    pub fn open<P: AsRef<Path>>(path: PathBuf, action_file: P) -> Result<History, Error> {
+        let action_file = action_file_key(action_file.as_ref());
+        let mut runs = read_runs(&path)?;
+        if runs.len() > MAX_RUNS {
+            // Other menus could add runs after the history was read, so it is read again while
+            // nobody else can write to it.
+            let _lock = lock(&path)?;
+            runs = read_runs(&path)?;
+            runs.drain(..runs.len().saturating_sub(MAX_RUNS));
+            write_runs(&path, &runs)?;
+        }
+        runs.retain(|run| run.action_file == action_file);
+
+        Ok(History {
+            path,
+            action_file,
+            runs,
+        })
+    }
+
+    /// Records that the entry on the page was run, with the result if tydra waited for it.
+    pub fn record(
+        &mut self,
+        page: &str,
+        entry: &Entry,
+        result: Option<&RunResult>,
+    ) -> Result<(), Error> {
+        let duration = result.map_or(Duration::from_secs(0), |result| result.duration);
+        let started = SystemTime::now() - duration;
+        let run = Run {
+            action_file: self.action_file.clone(),
+            page: page.to_owned(),
+            shortcut: entry.shortcut(),
+            id: entry.id().map(String::from),
+            title: entry.title().to_owned(),
+            mode: entry.runner_mode(),
+            started: started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            exit_code: result.and_then(|result| result.exit_status.code()),
+            signal: result.and_then(|result| result.exit_status.signal()),
+            duration_ms: result.map(|result| result.duration.as_millis() as u64),
+        };
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let _lock = lock(&self.path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&run)?)?;
+
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// Entries of the action file that have been run, the most recently used first, along with
+    /// the names of their pages. Entries that are no longer in the action file are left out.
+    pub fn recent_entries<'a>(&self, actions: &'a ActionFile) -> Vec<(&'a Entry, &'a str)> {
+        let mut entries: Vec<(&Entry, &str)> = Vec::new();
+        for found in self
+            .runs
+            .iter()
+            .rev()
+            .filter_map(|run| run.find_entry(actions))
+        {
+            if !entries.iter().any(|entry| same_entry(*entry, found)) {
+                entries.push(found);
+            }
+        }
+        entries
+    }
+
+    /// Like `recent_entries`, but the most often used entries come first. Entries that have been
+    /// used as often are listed in order of when they were last used.
+    pub fn frequent_entries<'a>(&self, actions: &'a ActionFile) -> Vec<(&'a Entry, &'a str)> {
+        let mut counted: Vec<((&Entry, &str), usize)> = Vec::new();
+        for found in self
+            .runs
+            .iter()
+            .rev()
+            .filter_map(|run| run.find_entry(actions))
+        {
+            match counted
+                .iter_mut()
+                .find(|(entry, _)| same_entry(*entry, found))
+            {
+                Some((_, count)) => *count += 1,
+                None => counted.push((found, 1)),
+            }
+        }
+        // The sort is stable, so the most recent entry still comes first on a tie.
+        counted.sort_by(|(_, a), (_, b)| b.cmp(a));
+        counted.into_iter().map(|(entry, _)| entry).collect()
+    }
+
+    /// The shortcut of the entry on the page that was run most recently, if it is still there.
+    pub fn last_used(&self, page: &Page, page_name: &str) -> Option<char> {
+        self.runs
+            .iter()
+            .rev()
+            .filter(|run| run.page == page_name)
+            .find_map(|run| {
+                let entry = match run.id {
+                    Some(ref id) => page.entry_with_id(id),
+                    None => page.entry_with_shortcut(run.shortcut),
+                };
+                entry.map(Entry::shortcut)
+            })
+    }
+}
+
+impl Run {
+    /// The entry that was run and the name of its page, if it is still in the action file.
+    fn find_entry<'a>(&self, actions: &'a ActionFile) -> Option<(&'a Entry, &'a str)> {
+        // Entries with an id are found even when they have moved to another page.
+        if let Some(ref id) = self.id {
+            return actions.entry_with_id(id);
+        }
+        let (page, page_name) = actions
+            .pages_with_names()
+            .find(|(_, name)| *name == self.page)?;
+        page.entry_with_shortcut(self.shortcut)
+            .map(|entry| (entry, page_name))
+    }
+
+    /// How the run went, like "Succeeded in 340ms".
+    pub fn outcome(&self) -> String {
+        let exit_status = match (self.exit_code, self.signal) {
+            (Some(code), _) => ExitStatus::from_raw(code << 8),
+            (None, Some(signal)) => ExitStatus::from_raw(signal),
+            (None, None) => {
+                return match self.mode {
+                    RunMode::Background => String::from("Started in the background"),
+                    RunMode::Exec => String::from("Started in place of tydra"),
+                    RunMode::Normal | RunMode::Wait => String::from("Started"),
+                    RunMode::Copy => String::from("Copied to the clipboard"),
+                    RunMode::Type => String::from("Typed"),
+                }
+            }
+        };
+        RunResult {
+            exit_status,
+            duration: Duration::from_millis(self.duration_ms.unwrap_or(0)),
+        }
+        .to_string()
+    }
+}
+
+fn same_entry(a: (&Entry, &str), b: (&Entry, &str)) -> bool {
+    a.1 == b.1 && a.0.shortcut() == b.0.shortcut()
+}
+
+/// The action file as it is stored in the history, which is its full path when it can be found.
+fn action_file_key(path: &Path) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_owned())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Reads all runs in the history file. Lines that cannot be read, like the last line of a file
+/// that was being written when the computer crashed, are skipped.
+pub fn read_runs(path: &Path) -> Result<Vec<Run>, Error> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(data
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Takes the lock on the history file, which is released when the returned file is closed. Runs
+/// are only added and the history is only trimmed while holding it, so that runs that other menus
+/// add while the history is trimmed are not lost. The lock is on a file next to the history, as
+/// trimming replaces the history file itself.
+fn lock(path: &Path) -> Result<fs::File, Error> {
+    use nix::fcntl::{flock, FlockArg};
+
+    let mut lock_name = path.file_name().unwrap_or_default().to_owned();
+    lock_name.push(".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(path.with_file_name(lock_name))?;
+    flock(file.as_raw_fd(), FlockArg::LockExclusive)?;
+    Ok(file)
+}
+
+/// Replaces the history file with the runs. They are written to a new file that is then renamed
+/// over the old one, so that the history is never left half written.
+fn write_runs(path: &Path, runs: &[Run]) -> Result<(), Error> {
+    let mut data = String::new();
+    for run in runs {
+        writeln!(data, "{}", serde_json::to_string(run)?)?;
+    }
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result?;
+    Ok(())
+}
+
+/// Lists the most recent runs of each action file, newest first. Only runs of the given action
+/// file are listed, if there is one.
+pub fn list(runs: &[Run], action_file: Option<&Path>, limit: usize, now: SystemTime) -> String {
+    let action_file = action_file.map(action_file_key);
+    let mut action_files: Vec<&str> = Vec::new();
+    for run in runs.iter().rev() {
+        let wanted = action_file
+            .as_ref()
+            .is_none_or(|file| *file == run.action_file);
+        if wanted && !action_files.contains(&run.action_file.as_str()) {
+            action_files.push(&run.action_file);
+        }
+    }
+
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let mut output = String::new();
+    for (index, file) in action_files.iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        output.push_str(file);
+        output.push('\n');
+
+        let lines: Vec<[String; 4]> = runs
+            .iter()
+            .rev()
+            .filter(|run| run.action_file == *file)
+            .take(limit)
+            .map(|run| {
+                [
+                    time_ago(now.saturating_sub(run.started)),
+                    format!("{}:{}", run.page, run.shortcut),
+                    run.title.clone(),
+                    run.outcome(),
+                ]
+            })
+            .collect();
+        let widths: Vec<usize> = (0..3)
+            .map(|column| {
+                let width = lines.iter().map(|line| line[column].chars().count());
+                width.max().unwrap_or(0)
+            })
+            .collect();
+        for [when, entry, title, outcome] in &lines {
+            // The last column is not padded, so lines do not end in spaces.
+            writeln!(
+                output,
+                "  {:>when_width$}  {:entry_width$}  {:title_width$}  {}",
+                when,
+                entry,
+                title,
+                outcome,
+                when_width = widths[0],
+                entry_width = widths[1],
+                title_width = widths[2],
+            )
+            .unwrap();
+        }
+    }
+    output
+}
+
+/// Describes how long ago something happened, as roughly as makes sense.
+fn time_ago(seconds: u64) -> String {
+    match seconds {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86_399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestDirectory;
+
+    fn page() -> Page {
+        serde_yaml::from_str(
+            r#"
+groups:
+  - entries:
+      - {shortcut: a, title: Apple, id: apple}
+      - {shortcut: b, title: Banana}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_records_runs_and_remembers_the_last_used_entry() {
+        let directory = TestDirectory::new("history-record");
+        let path = directory.join("history.jsonl");
+        let page = page();
+        let mut history = History::open(path.clone(), "actions.yml").unwrap();
+        assert_eq!(history.last_used(&page, "root"), None);
+
+        let result = RunResult {
+            exit_status: ExitStatus::from_raw(1 << 8),
+            duration: Duration::from_millis(1500),
+        };
+        history
+            .record("root", page.all_entries().next().unwrap(), Some(&result))
+            .unwrap();
+        history
+            .record("other", page.all_entries().nth(1).unwrap(), None)
+            .unwrap();
+
+        let history = History::open(path.clone(), "actions.yml").unwrap();
+        assert_eq!(history.last_used(&page, "root"), Some('a'));
+        assert_eq!(history.last_used(&page, "other"), Some('b'));
+        assert_eq!(history.runs[0].outcome(), "Exited with 1 after 1.5s");
+        assert_eq!(history.runs[1].outcome(), "Started");
+
+        // Other action files have histories of their own.
+        let history = History::open(path, "other.yml").unwrap();
+        assert_eq!(history.last_used(&page, "root"), None);
+    }
+
+    #[test]
+    fn it_keeps_only_the_most_recent_runs() {
+        let directory = TestDirectory::new("history-trim");
+        let path = directory.join("history.jsonl");
+        let mut data = String::new();
+        for started in 0..MAX_RUNS as u64 + 5 {
+            let run = Run {
+                action_file: String::from("/a.yml"),
+                page: String::from("root"),
+                shortcut: 'a',
+                id: None,
+                title: String::from("Apple"),
+                mode: RunMode::Normal,
+                started,
+                exit_code: None,
+                signal: None,
+                duration_ms: None,
+            };
+            writeln!(data, "{}", serde_json::to_string(&run).unwrap()).unwrap();
+        }
+        fs::write(&path, data).unwrap();
+
+        History::open(path.clone(), "/a.yml").unwrap();
+
+        let runs = read_runs(&path).unwrap();
+        assert_eq!(runs.len(), MAX_RUNS);
+        assert_eq!(runs[0].started, 5);
+        // The new history is written to a temporary file first, which is not left behind.
+        let leftovers = fs::read_dir(directory.path())
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("tmp".as_ref()))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn it_lists_recent_runs_per_action_file() {
+        let run = |action_file: &str, shortcut: char, title: &str, started: u64| Run {
+            action_file: action_file.to_owned(),
+            page: String::from("root"),
+            shortcut,
+            id: None,
+            title: title.to_owned(),
+            mode: RunMode::Normal,
+            started,
+            exit_code: Some(0),
+            signal: None,
+            duration_ms: Some(20),
+        };
+        let runs = vec![
+            run("/a.yml", 'x', "Oldest", 1_000),
+            run("/b.yml", 'y', "Other file", 5_000),
+            run("/a.yml", 'l', "Long title", 9_000),
+            run("/a.yml", 'n', "Newest", 9_990),
+        ];
+        let now = UNIX_EPOCH + Duration::from_secs(10_000);
+
+        assert_eq!(
+            list(&runs, None, 2, now),
+            "/a.yml
+  just now  root:n  Newest      Succeeded in 20ms
+   16m ago  root:l  Long title  Succeeded in 20ms
+
+/b.yml
+  1h ago  root:y  Other file  Succeeded in 20ms
+"
+        );
+    }
+}
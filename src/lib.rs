@@ -0,0 +1,62 @@
+//! Shortcut menus in the terminal, inspired by Emacs Hydra.
+//!
+//! This is the library behind the `tydra` command. Menus can be loaded from action files, or be
+//! built in code, and then shown with a [`Menu`]:
+//!
+//! ```no_run
+//! use tydra::actions::{ActionFile, Entry, Group, Page, Return};
+//! use tydra::Menu;
+//!
+//! let actions = ActionFile::new().with_page(
+//!     "root",
+//!     Page::new("Deploy").with_group(
+//!         Group::new()
+//!             .with_entry(Entry::new('s', "Staging").with_id("staging"))
+//!             .with_entry(Entry::new('p', "Production").with_id("production"))
+//!             .with_entry(
+//!                 Entry::new('l', "Show the log")
+//!                     .with_command("git log --oneline -5")
+//!                     .with_return(Return::SamePage),
+//!             ),
+//!     ),
+//! );
+//!
+//! if let Some(choice) = Menu::new(actions).inline(6).run()? {
+//!     println!("Deploying to {}", choice.entry.id().unwrap());
+//! }
+//! # Ok::<(), failure::Error>(())
+//! ```
+
+// failure_derive places the impls of #[derive(Fail)] inside of anonymous constants.
+#![allow(non_local_definitions)]
+
+#[macro_use]
+extern crate failure;
+
+#[macro_use]
+extern crate failure_derive;
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod actions;
+mod backend;
+pub mod choice;
+mod clipboard;
+mod events;
+pub mod graph;
+pub mod headless;
+pub mod history;
+pub mod listing;
+mod menu;
+mod recent;
+mod runner;
+mod status;
+#[cfg(test)]
+mod testing;
+mod watcher;
+pub mod widget;
+
+pub use crate::choice::Choice;
+pub use crate::history::History;
+pub use crate::menu::{Menu, DEFAULT_INLINE_HEIGHT};
@@ -0,0 +1,352 @@
+use crate::actions::{ActionFile, Command, Entry, Return, RunMode};
+use failure::Error;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The formats that the menu can be listed in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
+impl ListFormat {
+    pub fn variants() -> [&'static str; 3] {
+        ["text", "json", "markdown"]
+    }
+}
+
+impl FromStr for ListFormat {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<ListFormat, Error> {
+        match string {
+            "text" => Ok(ListFormat::Text),
+            "json" => Ok(ListFormat::Json),
+            "markdown" => Ok(ListFormat::Markdown),
+            other => Err(format_err!("Unknown list format: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ListedPage<'a> {
+    name: &'a str,
+    title: &'a str,
+    groups: Vec<ListedGroup<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListedGroup<'a> {
+    title: Option<&'a str>,
+    entries: Vec<ListedEntry<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListedEntry<'a> {
+    shortcut: char,
+    id: Option<&'a str>,
+    title: &'a str,
+    description: Option<&'a str>,
+    value: &'a str,
+    mode: RunMode,
+    command: &'a Command,
+    #[serde(rename = "return")]
+    return_to: &'a Return,
+}
+
+/// Lists every page, group and entry of the action file in the given format.
+pub fn list(actions: &ActionFile, format: ListFormat) -> String {
+    let pages = listed_pages(actions);
+    match format {
+        ListFormat::Text => list_text(&pages),
+        ListFormat::Json => {
+            // The listing only holds strings, lists and simple values so it cannot fail.
+            let mut json = serde_json::to_string_pretty(&pages).unwrap();
+            json.push('\n');
+            json
+        }
+        ListFormat::Markdown => list_markdown(&pages),
+    }
+}
+
+fn listed_pages(actions: &ActionFile) -> Vec<ListedPage<'_>> {
+    actions
+        .pages_with_names()
+        .map(|(page, name)| ListedPage {
+            name,
+            title: page.title(),
+            groups: page
+                .groups()
+                .iter()
+                .map(|group| ListedGroup {
+                    title: group.title(),
+                    entries: group.entries().iter().map(listed_entry).collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn listed_entry(entry: &Entry) -> ListedEntry<'_> {
+    ListedEntry {
+        shortcut: entry.shortcut(),
+        id: entry.id(),
+        title: entry.title(),
+        description: entry.description(),
+        value: entry.value(),
+        mode: entry.runner_mode(),
+        command: entry.command(),
+        return_to: entry.return_to(),
+    }
+}
+
+fn list_text(pages: &[ListedPage]) -> String {
+    let mut text = String::new();
+
+    for page in pages {
+        writeln!(text, "{}: {}", page.name, page.title).unwrap();
+        for group in &page.groups {
+            if let Some(title) = group.title {
+                writeln!(text, "  {}:", title).unwrap();
+            }
+            for entry in &group.entries {
+                writeln!(
+                    text,
+                    "    [{}] {} ({}, return: {})",
+                    entry.shortcut, entry.title, entry.mode, entry.return_to
+                )
+                .unwrap();
+                if *entry.command != Command::None {
+                    for line in entry.command.to_string().lines() {
+                        writeln!(text, "        {}", line).unwrap();
+                    }
+                }
+            }
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+fn list_markdown(pages: &[ListedPage]) -> String {
+    let mut text = String::new();
+
+    for page in pages {
+        writeln!(text, "## {} ({})\n", page.title, markdown_code(page.name)).unwrap();
+        for group in &page.groups {
+            if let Some(title) = group.title {
+                writeln!(text, "### {}\n", title).unwrap();
+            }
+            text.push_str("| Shortcut | Title | Mode | Command | Return |\n");
+            text.push_str("|---|---|---|---|---|\n");
+            for entry in &group.entries {
+                writeln!(
+                    text,
+                    "| {} | {} | {} | {} | {} |",
+                    markdown_cell(&markdown_code(&entry.shortcut.to_string())),
+                    markdown_cell(entry.title),
+                    entry.mode,
+                    markdown_command(entry.command),
+                    markdown_cell(&entry.return_to.to_string()),
+                )
+                .unwrap();
+            }
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+fn markdown_command(command: &Command) -> String {
+    match *command {
+        Command::None => String::new(),
+        // Table cells cannot span lines, so show each line of scripts as a separate code span.
+        _ => command
+            .to_string()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| markdown_cell(&markdown_code(line.trim())))
+            .collect::<Vec<_>>()
+            .join("<br>"),
+    }
+}
+
+/// Wraps the text in a code span, using a run of backticks longer than any run inside of the text
+/// so that shortcuts and commands with backticks do not end the span early.
+fn markdown_code(text: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for character in text.chars() {
+        if character == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+
+    let fence = "`".repeat(longest + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", fence, text, fence)
+    } else {
+        format!("{}{}{}", fence, text, fence)
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate serde_yaml;
+
+    fn actions() -> ActionFile {
+        serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    title: Welcome
+    groups:
+      - title: Things
+        entries:
+          - shortcut: a
+            title: Run a | b
+            description: Pipes a into b
+            command: "a | b"
+          - shortcut: p
+            id: packages
+            title: Packages
+            value: pkg
+            return: packages
+  packages:
+    groups:
+      - entries:
+          - shortcut: u
+            title: Update
+            mode: wait
+            return: true
+            command: |
+              sudo pacman -Syu
+              echo Done"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_lists_as_text() {
+        assert_eq!(
+            list(&actions(), ListFormat::Text),
+            "\
+packages: Tydra
+    [u] Update (wait, return: same page)
+        sudo pacman -Syu
+        echo Done
+
+root: Welcome
+  Things:
+    [a] Run a | b (normal, return: quit)
+        a | b
+    [p] Packages (normal, return: page packages)
+
+"
+        );
+    }
+
+    #[test]
+    fn it_lists_as_markdown() {
+        assert_eq!(
+            list(&actions(), ListFormat::Markdown),
+            "\
+## Tydra (`packages`)
+
+| Shortcut | Title | Mode | Command | Return |
+|---|---|---|---|---|
+| `u` | Update | wait | `sudo pacman -Syu`<br>`echo Done` | same page |
+
+## Welcome (`root`)
+
+### Things
+
+| Shortcut | Title | Mode | Command | Return |
+|---|---|---|---|---|
+| `a` | Run a \\| b | normal | `a \\| b` | quit |
+| `p` | Packages | normal |  | page packages |
+
+"
+        );
+    }
+
+    #[test]
+    fn it_lists_as_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&list(&actions(), ListFormat::Json)).unwrap();
+
+        assert_eq!(
+            json[1],
+            serde_json::json!({
+                "name": "root",
+                "title": "Welcome",
+                "groups": [{
+                    "title": "Things",
+                    "entries": [
+                        {
+                            "shortcut": "a",
+                            "id": null,
+                            "title": "Run a | b",
+                            "description": "Pipes a into b",
+                            "value": "Run a | b",
+                            "mode": "normal",
+                            "command": "a | b",
+                            "return": false,
+                        },
+                        {
+                            "shortcut": "p",
+                            "id": "packages",
+                            "title": "Packages",
+                            "description": null,
+                            "value": "pkg",
+                            "mode": "normal",
+                            "command": null,
+                            "return": "packages",
+                        },
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn it_escapes_backticks_in_markdown_code() {
+        let actions: ActionFile = serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    title: Welcome
+    groups:
+      - entries:
+          - shortcut: "`"
+            title: Date
+            command: "echo `date`"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            list(&actions, ListFormat::Markdown),
+            "\
+## Welcome (`root`)
+
+| Shortcut | Title | Mode | Command | Return |
+|---|---|---|---|---|
+| `` ` `` | Date | normal | `` echo `date` `` | quit |
+
+"
+        );
+    }
+}
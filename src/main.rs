@@ -1,34 +1,48 @@
-#[macro_use]
-extern crate failure;
-
-#[macro_use]
-extern crate failure_derive;
-
-#[macro_use]
-extern crate serde_derive;
-
 extern crate structopt;
 
-mod actions;
-mod runner;
-
-use actions::{render, Action, ActionFile, Page, Return};
 use failure::Error;
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::io::FromRawFd;
+use std::time::SystemTime;
 use structopt::clap::Shell;
 use structopt::StructOpt;
-use termion::event;
-use tui::backend::AlternateScreenBackend;
-use tui::Terminal;
-
-type Term = Terminal<AlternateScreenBackend>;
+use tydra::actions::{self, ActionFile, Command, Entry, Group, Page, RunMode};
+use tydra::choice::ChoiceFormat;
+use tydra::graph::{self, GraphFormat};
+use tydra::history::{self, History};
+use tydra::listing::{self, ListFormat};
+use tydra::widget::{self, WidgetShell};
+use tydra::{headless, Menu, DEFAULT_INLINE_HEIGHT};
 
 #[derive(Debug, StructOpt)]
-#[structopt(setting = structopt::clap::AppSettings::ColoredHelp)]
+#[structopt(
+    setting = structopt::clap::AppSettings::ColoredHelp,
+    setting = structopt::clap::AppSettings::SubcommandsNegateReqs
+)]
 pub struct AppOptions {
     /// Read menu contents from this file.
-    #[structopt(value_name = "ACTION_FILE", required_unless = "generate-completions")]
+    #[structopt(
+        value_name = "ACTION_FILE",
+        required_unless_one = &["generate-completions", "shell-widget", "entries"]
+    )]
     filename: Option<String>,
 
+    /// Show a menu with this entry instead of reading a file. Can be given several times. Entries
+    /// with a command replace tydra with it, and the others close the menu.
+    #[structopt(
+        long = "entry",
+        value_name = "KEY:TITLE[:COMMAND]",
+        number_of_values = 1,
+        conflicts_with_all = &["filename", "watch"],
+        parse(try_from_str = parse_entry)
+    )]
+    entries: Vec<Entry>,
+
+    /// Title of the menu made with --entry.
+    #[structopt(long = "title", value_name = "TITLE", requires = "entries")]
+    title: Option<String>,
+
     /// Start on this page.
     #[structopt(long = "page", short = "p", default_value = "root")]
     start_page: String,
@@ -41,6 +55,62 @@ pub struct AppOptions {
     #[structopt(long = "ignore-exit-status", short = "e")]
     ignore_exit_status: bool,
 
+    /// Instead of showing the menu, print all pages and entries in it.
+    #[structopt(long = "list", conflicts_with_all = &["run", "watch"])]
+    list: bool,
+
+    /// Format to print the menu in when using --list.
+    #[structopt(
+        long = "list-format",
+        value_name = "FORMAT",
+        default_value = "text",
+        possible_values = &ListFormat::variants()
+    )]
+    list_format: ListFormat,
+
+    /// Run a single entry without showing the menu, then exit with the exit status of its
+    /// command. Given as PAGE:SHORTCUT, PAGE:ID, ID, or a SHORTCUT on the start page. PAGE/KEY
+    /// works as well.
+    #[structopt(
+        long = "run",
+        short = "r",
+        value_name = "ENTRY",
+        conflicts_with = "watch"
+    )]
+    run: Option<String>,
+
+    /// Instead of running the command of the entry that is picked, print the entry on STDOUT and
+    /// exit. Exits with 1 when the menu is closed without picking anything, and with 2 when the
+    /// action file cannot be loaded or something else goes wrong.
+    #[structopt(long = "print-choice", conflicts_with_all = &["run", "list"])]
+    print_choice: bool,
+
+    /// What to print about the picked entry with --print-choice.
+    #[structopt(
+        long = "choice-format",
+        value_name = "FORMAT",
+        default_value = "value",
+        possible_values = &ChoiceFormat::variants()
+    )]
+    choice_format: ChoiceFormat,
+
+    /// Reload the action file when it changes on disk, while the menu is open.
+    #[structopt(long = "watch", short = "w")]
+    watch: bool,
+
+    /// Show the menu in the lines below the cursor instead of the whole screen, and erase it
+    /// again when exiting.
+    #[structopt(long = "inline", short = "i")]
+    inline: bool,
+
+    /// Number of lines the menu takes up when using --inline. Defaults to 10.
+    #[structopt(long = "height", value_name = "LINES", requires = "inline")]
+    height: Option<u16>,
+
+    /// Do not record the entries that are run in the history.
+    #[structopt(long = "no-history")]
+    no_history: bool,
+
     /// Generate completion script for a given shell and output on STDOUT.
     #[structopt(
         long = "generate-completions",
@@ -48,6 +118,92 @@ pub struct AppOptions {
         possible_values = &Shell::variants()
     )]
     generate_completions: Option<Shell>,
+
+    /// Print a shell function on STDOUT that shows the menu when pressing Alt-T, and inserts the
+    /// command of the picked entry into the command line instead of running it. Without an
+    /// action file, the function uses $TYDRA_ACTION_FILE.
+    #[structopt(
+        long = "shell-widget",
+        value_name = "SHELL",
+        possible_values = &WidgetShell::variants()
+    )]
+    shell_widget: Option<WidgetShell>,
+
+    #[structopt(subcommand)]
+    command: Option<AppCommand>,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum AppCommand {
+    /// Print a JSON Schema describing the action file format on STDOUT.
+    ///
+    /// Point your editor's YAML language server to it to get completion and validation when
+    /// editing action files.
+    #[structopt(name = "schema")]
+    Schema,
+
+    /// Print the navigation graph between the pages of an action file.
+    ///
+    /// Pages are nodes and entries that return to other pages are edges, labeled with their
+    /// shortcuts. Pages that cannot be reached from the start page are highlighted.
+    #[structopt(name = "graph")]
+    Graph {
+        /// Format of the graph; Graphviz (dot) or Mermaid.
+        #[structopt(
+            long = "format",
+            short = "f",
+            value_name = "FORMAT",
+            default_value = "dot",
+            possible_values = &GraphFormat::variants()
+        )]
+        format: GraphFormat,
+
+        /// Page that the menu starts on.
+        #[structopt(long = "page", short = "p", default_value = "root")]
+        start_page: String,
+
+        /// Read menu contents from this file.
+        #[structopt(value_name = "ACTION_FILE")]
+        filename: String,
+    },
+
+    /// Run a single entry without showing the menu, then exit with the exit status of its command.
+    ///
+    /// This works like --run, for scripts and cron jobs that share the menu's action file.
+    #[structopt(name = "run")]
+    Run {
+        /// The entry to run, given as PAGE/SHORTCUT, PAGE/ID, ID, or a SHORTCUT on the start page.
+        #[structopt(value_name = "ENTRY")]
+        target: String,
+
+        /// Page that shortcuts without a page are looked up on.
+        #[structopt(long = "page", short = "p", default_value = "root")]
+        start_page: String,
+
+        /// Read menu contents from this file.
+        #[structopt(value_name = "ACTION_FILE", env = "TYDRA_ACTION_FILE")]
+        filename: String,
+    },
+
+    /// Print the entries that were run most recently, per action file.
+    ///
+    /// The history is kept in $XDG_STATE_HOME/tydra/history.jsonl, which defaults to
+    /// ~/.local/state/tydra/history.jsonl.
+    #[structopt(name = "history")]
+    History {
+        /// Number of runs to print for each action file.
+        #[structopt(
+            long = "limit",
+            short = "n",
+            value_name = "COUNT",
+            default_value = "10"
+        )]
+        limit: usize,
+
+        /// Only print the runs of this action file.
+        #[structopt(value_name = "ACTION_FILE")]
+        filename: Option<String>,
+    },
 }
 
 fn generate_completions(shell: structopt::clap::Shell) {
@@ -58,6 +214,26 @@ fn generate_completions(shell: structopt::clap::Shell) {
     app.gen_completions_to(name, shell, &mut io::stdout());
 }
 
+/// Prints the shell widget. The action file is given as an absolute path, as the widget is used
+/// from any directory.
+fn print_shell_widget(shell: WidgetShell, filename: Option<&str>) {
+    let action_file = match filename.map(std::fs::canonicalize) {
+        Some(Ok(path)) => Some(path),
+        Some(Err(error)) => {
+            eprintln!("Error while loading \"{}\": {}", filename.unwrap(), error);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+    print!("{}", widget::shell_widget(shell, action_file.as_deref()));
+}
+
+fn print_schema() {
+    let schema = schemars::schema_for!(ActionFile);
+    // Serializing a schema cannot fail; it only contains strings, maps and plain values.
+    println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
+
 fn main() {
     let options = AppOptions::from_args();
 
@@ -66,272 +242,263 @@ fn main() {
         return;
     }
 
-    // Because filename should only ever be None if passed generate_completions options (thanks to
-    // required_unless), it should be safe to unwrap after checking for generate_completions.
-    let filename = options.filename.as_ref().unwrap();
+    if let Some(shell) = options.shell_widget {
+        print_shell_widget(shell, options.filename.as_deref());
+        return;
+    }
 
-    let actions: ActionFile = match load_actions_from_path(filename) {
-        Ok(actions) => actions,
-        Err(error) => {
-            eprintln!("Error while loading \"{}\": {}", filename, error);
-            for cause in error.iter_causes() {
-                eprintln!("Caused by: {}", cause);
-            }
+    match options.command {
+        Some(AppCommand::Schema) => {
+            print_schema();
             return;
         }
-    };
-
-    // Validate the action file so it is semantically correct before continuing.
-    if let Err(errors) = actions.validate(&options) {
-        print_validation_errors(&errors);
-        std::process::exit(1);
+        Some(AppCommand::Graph {
+            format,
+            ref start_page,
+            ref filename,
+        }) => {
+            print_graph(filename, start_page, format);
+            return;
+        }
+        Some(AppCommand::Run {
+            ref target,
+            ref start_page,
+            ref filename,
+        }) => {
+            let actions = load_valid_actions(filename, start_page, 1);
+            run_single_entry(&actions, target, start_page);
+        }
+        Some(AppCommand::History {
+            limit,
+            ref filename,
+        }) => {
+            print_history(filename.as_deref(), limit);
+            return;
+        }
+        None => {}
     }
 
+    // With --print-choice, a status of 1 means that nothing was picked, so problems get their own
+    // status for scripts to tell them apart.
+    let failure_status = if options.print_choice { 2 } else { 1 };
+
+    // Because filename should only ever be None if passed generate_completions or shell_widget
+    // options, entries or a subcommand (thanks to required_unless_one and
+    // SubcommandsNegateReqs), it should be safe to unwrap after checking for those.
+    let actions = if options.entries.is_empty() {
+        let filename = options.filename.as_ref().unwrap();
+        load_valid_actions(filename, &options.start_page, failure_status)
+    } else {
+        let actions = actions_from_entries(options.title.as_deref(), &options.entries);
+        exit_if_invalid(&actions, &options.start_page, failure_status);
+        actions
+    };
+
     // If running in validation mode, exit with a message after passing validations.
     if options.validate {
         eprintln!("File is valid.");
         std::process::exit(0);
     }
 
-    // Run the menu. If it fails, then print the error message.
-    if let Err(error) = run_menu(&actions, &options) {
-        flush_terminal();
-        eprintln!("Error: {}", error);
-        for cause in error.iter_causes() {
-            eprintln!("Caused by: {}", cause);
-        }
+    // Print the menu instead of showing it, if asked to.
+    if options.list {
+        print!("{}", listing::list(&actions, options.list_format));
+        return;
     }
-}
 
-fn load_actions_from_path(path: &str) -> Result<ActionFile, Error> {
-    std::fs::read_to_string(path)
-        .map_err(Error::from)
-        .and_then(|data| serde_yaml::from_str(&data).map_err(Error::from))
-}
-
-fn flush_terminal() {
-    // Flush the output from Terminal being dropped; this is not done by termion itself.
-    // https://gitlab.redox-os.org/redox-os/termion/issues/158
-    //
-    // Printing to stderr before stdout is flushed, or letting other processes write to it,
-    // means that the text ends up on the alternate screen that will be removed as soon as *our*
-    // stdout buffer is flushed.
-    use std::io::Write;
-    ::std::io::stdout().flush().ok();
-}
+    // Run a single entry without the menu, if asked to.
+    if let Some(ref target) = options.run {
+        run_single_entry(&actions, target, &options.start_page);
+    }
 
-/// Wrapper around an AlternateScreen terminal, that handles restoration on drop.
-struct TermHandle(Term);
+    // The shell usually captures the output when picking an entry or typing text, so the menu is
+    // shown on the terminal itself.
+    let mut output = match use_terminal_for_menu() {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("Error: Could not open the terminal: {}", error);
+            std::process::exit(failure_status);
+        }
+    };
 
-impl TermHandle {
-    /// Opens the terminal's "Alternate screen" and hide the cursor.
-    ///
-    /// This is like a separate screen that you can ouput to freely, and when this screen is closed
-    /// the previous screen is restored. Most terminal UIs use this in order to not clobber output
-    /// from earlier commands. For example, run vim and exit it again and you can see that your
-    /// terminal is restored to look like it did before you started vim.
-    ///
-    /// Will restore cursor when dropped.
-    fn new() -> Result<TermHandle, Error> {
-        let backend = AlternateScreenBackend::new()?;
-        let mut terminal = Terminal::new(backend)?;
-        terminal.hide_cursor()?;
-        terminal.clear()?;
-        Ok(TermHandle(terminal))
+    // Run the menu. If it fails, then print the error message.
+    let mut menu = Menu::new(actions)
+        .start_page(options.start_page.as_str())
+        .ignore_exit_status(options.ignore_exit_status)
+        .picker(options.print_choice);
+    if let (true, Some(filename)) = (options.watch, options.filename.as_ref()) {
+        menu = menu.watch(filename);
     }
-
-    fn restart(self) -> Result<TermHandle, Error> {
-        TermHandle::new()
+    if options.inline {
+        menu = menu.inline(options.height.unwrap_or(DEFAULT_INLINE_HEIGHT));
     }
-}
-
-impl Drop for TermHandle {
-    fn drop(&mut self) {
-        self.0.show_cursor().ok();
+    // Menus given with --entry are different every time, so they have no history.
+    if let (false, Some(filename)) = (options.no_history, options.filename.as_ref()) {
+        if let Some(history) = open_history(filename) {
+            menu = menu.history(history);
+        }
     }
-}
-
-/// Starts the main event loop.
-///
-/// Start:
-///     Begin on root page.
-///     Open alternate screen.
-/// Loop:
-///     Render menu.
-///     Wait for a valid input.
-///     Process input's event, possibly running a command.
-///     Wait for user to press enter, if waiting is enabled.
-///     Update which page to be on.
-///     Exit if event tells us to.
-///     Repeat loop.
-/// End:
-///     Restore screen.
-///
-fn run_menu(actions: &ActionFile, options: &AppOptions) -> Result<(), Error> {
-    // Code in this function is annotated according to the function documentation comment to help
-    // navigate it. It is quite big, sadly.
-
-    // Start
-    let error_on_failure = !options.ignore_exit_status;
-    let settings = actions.settings_accumulator();
-    let mut current_page = actions.get_page(&options.start_page);
-    let mut page_settings = settings.with_page(&current_page);
-
-    let mut terminal = TermHandle::new()?;
-
-    // Loop
-    loop {
-        render(&mut terminal.0, current_page, &page_settings)?;
-
-        // Wait for an event from user input.
-        let action = process_input(current_page)?;
-        let return_to = match action {
-            // Quit / Exit.
-            Action::Exit => Return::Quit,
-
-            // Redraw menu.
-            Action::Redraw => {
-                terminal = terminal.restart()?;
-                Return::SamePage
-            }
-
-            Action::Pause => {
-                terminal = pause_tydra(terminal)?;
-                Return::SamePage
+    match menu.run() {
+        Ok(Some(choice)) if options.print_choice => {
+            writeln!(output, "{}", choice.format(options.choice_format)).ok();
+        }
+        Ok(Some(choice)) if choice.entry.runner_mode() == RunMode::Type => {
+            let text = choice.entry.snippet().unwrap_or_default();
+            writeln!(output, "{}", text).ok();
+        }
+        Ok(None) if options.print_choice => std::process::exit(1),
+        Ok(_) => {}
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            for cause in error.iter_causes() {
+                eprintln!("Caused by: {}", cause);
             }
-
-            // Run a command in normal mode, e.g. pause tydra and run the command. Return to tydra
-            // after the command exits.
-            Action::Run {
-                command,
-                return_to,
-                wait,
-            } => {
-                terminal = run_normal(terminal, error_on_failure, command, wait)?;
-                return_to
-            }
-
-            // Replace tydra with the command's process.
-            // If it returns, it has to be an error.
-            Action::RunExec { command } => return Err(run_exec(terminal, command)),
-
-            // Run command in background and immediately return to the menu again.
-            Action::RunBackground { command, return_to } => unsafe {
-                runner::run_background(&command)?;
-                return_to
-            },
-        };
-
-        // Decide on which page to render now.
-        match return_to {
-            Return::Quit => break,
-            Return::SamePage => continue,
-            Return::OtherPage(page_name) => {
-                current_page = actions.get_page(&page_name);
-                page_settings = settings.with_page(&current_page);
+            if options.print_choice {
+                std::process::exit(failure_status);
             }
         }
     }
-
-    Ok(())
 }
 
-fn run_normal(
-    terminal: TermHandle,
-    error_on_failure: bool,
-    command: actions::Command,
-    wait: bool,
-) -> Result<TermHandle, Error> {
-    // Run commands on the normal screen. This preserves the command's output even
-    // after tydra exits.
-    drop(terminal);
-    flush_terminal();
-
-    match runner::run_normal(&command) {
-        Some(Ok(exit_status)) => {
-            if error_on_failure && !exit_status.success() {
-                return Err(format_err!(
-                    "Command exited with exit status {}: {}",
-                    exit_status.code().unwrap_or(1),
-                    command
-                ));
+/// Makes sure that the menu is drawn on, and reads keys from, the terminal even when stdin or
+/// stdout are redirected. Returns the original stdout.
+fn use_terminal_for_menu() -> Result<File, Error> {
+    use nix::fcntl::{open, OFlag};
+    use nix::sys::stat::Mode;
+    use nix::unistd::{close, dup, dup2, isatty};
+
+    let output = unsafe { File::from_raw_fd(dup(1)?) };
+    let is_terminal = |fd| isatty(fd).unwrap_or(false);
+    if !is_terminal(0) || !is_terminal(1) {
+        let terminal = open("/dev/tty", OFlag::O_RDWR | OFlag::O_CLOEXEC, Mode::empty())?;
+        for &fd in &[0, 1] {
+            if !is_terminal(fd) {
+                dup2(terminal, fd)?;
             }
         }
-        Some(Err(err)) => return Err(err),
-        None => {}
+        close(terminal)?;
     }
+    Ok(output)
+}
 
-    if wait {
-        wait_for_confirmation()?;
+/// Runs the entry without the menu, and exits with the exit status of its command.
+fn run_single_entry(actions: &ActionFile, target: &str, start_page: &str) -> ! {
+    let result = headless::find_entry(actions, target, start_page)
+        .and_then(|(entry, _)| headless::run_entry(entry));
+    match result {
+        Ok(exit_status) => std::process::exit(exit_status),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            for cause in error.iter_causes() {
+                eprintln!("Caused by: {}", cause);
+            }
+            std::process::exit(1);
+        }
     }
-
-    TermHandle::new()
 }
 
-// Can use `!` when it is stable; it never returns a non-error
-fn run_exec(terminal: TermHandle, command: actions::Command) -> Error {
-    // Restore screen for the new command.
-    drop(terminal);
-    flush_terminal();
-
-    // If this returns, then it failed to exec the process so wrap that value in a
-    // error.
-    runner::run_exec(&command)
+fn print_graph(filename: &str, start_page: &str, format: GraphFormat) {
+    let actions = load_valid_actions(filename, start_page, 1);
+    print!("{}", graph::graph(&actions, start_page, format));
 }
 
-fn pause_tydra(terminal: TermHandle) -> Result<TermHandle, Error> {
-    use nix::sys::signal::{kill, Signal};
-    use nix::unistd::Pid;
-
-    drop(terminal);
-    flush_terminal();
-
-    // Tell this process to pause (standard ^Z signal)
-    kill(Pid::this(), Signal::SIGTSTP)?;
+/// Opens the history of the action file. The menu works without one, so problems are only
+/// printed as a warning.
+fn open_history(filename: &str) -> Option<History> {
+    let path = History::default_path()?;
+    match History::open(path, filename) {
+        Ok(history) => Some(history),
+        Err(error) => {
+            eprintln!("Warning: Could not read the history: {}", error);
+            None
+        }
+    }
+}
 
-    // Now the process is running again. Restore the terminal!
-    TermHandle::new()
+fn print_history(filename: Option<&str>, limit: usize) {
+    let path = match History::default_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("Error: Neither XDG_STATE_HOME nor HOME is set");
+            std::process::exit(1);
+        }
+    };
+    match history::read_runs(&path) {
+        Ok(runs) => {
+            let filename = filename.map(std::path::Path::new);
+            print!(
+                "{}",
+                history::list(&runs, filename, limit, SystemTime::now())
+            );
+        }
+        Err(error) => {
+            eprintln!("Error while reading \"{}\": {}", path.display(), error);
+            std::process::exit(1);
+        }
+    }
 }
 
-/// Reads input events until a valid event is found and returns it as an Action. Reads actions from
-/// provided page to determine what events are valid.
-fn process_input(page: &Page) -> Result<Action, Error> {
-    use termion::input::TermRead;
-    let stdin = std::io::stdin();
-
-    // Iterate all valid events
-    for event in stdin.keys().flat_map(Result::ok) {
-        match event {
-            event::Key::Esc => return Ok(Action::Exit),
-            event::Key::Ctrl('l') => return Ok(Action::Redraw),
-            event::Key::Ctrl('z') => return Ok(Action::Pause),
-            event::Key::Char(chr) => {
-                if let Some(entry) = page.entry_with_shortcut(chr) {
-                    return Ok(entry.into());
-                }
+/// Loads the action file and validates it so it is semantically correct before continuing.
+///
+/// Problems are printed on stderr. If the file cannot be loaded or is invalid, the process exits
+/// with the given status.
+fn load_valid_actions(filename: &str, start_page: &str, failure_status: i32) -> ActionFile {
+    let actions: ActionFile = match ActionFile::load(filename) {
+        Ok(actions) => actions,
+        Err(error) => {
+            eprintln!("Error while loading \"{}\": {}", filename, error);
+            for cause in error.iter_causes() {
+                eprintln!("Caused by: {}", cause);
             }
-            _ => {}
+            std::process::exit(failure_status);
         }
-    }
+    };
 
-    Err(format_err!("stdin was closed."))
+    exit_if_invalid(&actions, start_page, failure_status);
+    actions
 }
 
-/// Waits for the user to press Enter (or Escape, just to be nice) before returning.
-fn wait_for_confirmation() -> Result<(), Error> {
-    use termion::input::TermRead;
-    let stdin = std::io::stdin();
-
-    println!("Press enter to continue... ");
+/// Prints the validation errors on stderr and exits with the given status, if the actions are
+/// invalid.
+fn exit_if_invalid(actions: &ActionFile, start_page: &str, failure_status: i32) {
+    if let Err(errors) = actions.validate(start_page) {
+        print_validation_errors(&errors);
+        std::process::exit(failure_status);
+    }
+}
 
-    for event in stdin.keys().flat_map(Result::ok) {
-        match event {
-            event::Key::Char('\n') | event::Key::Esc => return Ok(()),
-            _ => {}
+/// Parses an entry given with --entry, like "y:Yes" or "l:List files:ls -l". Only the first two
+/// colons separate the parts, so commands can contain colons.
+fn parse_entry(spec: &str) -> Result<Entry, String> {
+    let mut parts = spec.splitn(3, ':');
+    let mut shortcut = parts.next().unwrap_or_default().chars();
+    let (shortcut, title) = match (shortcut.next(), shortcut.next(), parts.next()) {
+        (Some(shortcut), None, Some(title)) if !title.is_empty() => (shortcut, title),
+        _ => {
+            return Err(format!(
+                "Entries must be given as KEY:TITLE or KEY:TITLE:COMMAND, not \"{}\"",
+                spec
+            ))
         }
-    }
+    };
+
+    let entry = Entry::new(shortcut, title);
+    Ok(match parts.next() {
+        Some(command) if !command.is_empty() => entry
+            .with_command(Command::from(command))
+            .with_mode(RunMode::Exec),
+        _ => entry,
+    })
+}
 
-    Err(format_err!("stdin was closed."))
+/// Makes an action file with a single page for the entries given with --entry.
+fn actions_from_entries(title: Option<&str>, entries: &[Entry]) -> ActionFile {
+    let group = entries
+        .iter()
+        .cloned()
+        .fold(Group::new(), |group, entry| group.with_entry(entry));
+    let page = Page::new(title.unwrap_or("Tydra")).with_group(group);
+    ActionFile::new().with_page("root", page)
 }
 
 fn print_validation_errors(errors: &[actions::ValidationError]) {
@@ -340,3 +507,25 @@ fn print_validation_errors(errors: &[actions::ValidationError]) {
         eprintln!("  {number}. {message}", number = index + 1, message = error);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_entries_from_arguments() {
+        let entry = parse_entry("y:Yes").unwrap();
+        assert_eq!((entry.shortcut(), entry.title()), ('y', "Yes"));
+        assert_eq!(entry.command(), &Command::None);
+
+        let entry = parse_entry("t:Time:date +%H:%M").unwrap();
+        assert_eq!(entry.title(), "Time");
+        assert_eq!(entry.command(), &Command::from("date +%H:%M"));
+        assert_eq!(entry.runner_mode(), RunMode::Exec);
+
+        assert!(parse_entry("yes:Yes").is_err());
+        assert!(parse_entry("y").is_err());
+        assert!(parse_entry("y:").is_err());
+        assert!(parse_entry(":Yes").is_err());
+    }
+}
@@ -0,0 +1,609 @@
+use crate::actions::{
+    render, render_help, Action, ActionFile, Command, Entry, Page, Return, RunMode, HELP_KEY,
+    REPEAT_KEY,
+};
+use crate::backend::{Screen, ScreenBackend};
+use crate::choice::Choice;
+use crate::clipboard;
+use crate::events::{Event, Events, RawStdin};
+use crate::history::History;
+use crate::recent::{is_virtual_page, VirtualPage};
+use crate::runner;
+use crate::status::{RunResult, Status};
+use crate::watcher::Watcher;
+use failure::Error;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use termion::event;
+use tui::Terminal;
+
+type Term = Terminal<ScreenBackend>;
+
+/// Lines that an inline menu takes up, unless another height is given.
+pub const DEFAULT_INLINE_HEIGHT: u16 = 10;
+
+/// A menu to show in the terminal, for the user to pick entries from.
+///
+/// The menu runs the commands of the entries that are picked, just like the `tydra` command does,
+/// until an entry or the user closes it.
+pub struct Menu {
+    actions: ActionFile,
+    start_page: String,
+    ignore_exit_status: bool,
+    watch: Option<PathBuf>,
+    screen: Screen,
+    picker: bool,
+    history: Option<History>,
+}
+
+impl Menu {
+    pub fn new(actions: ActionFile) -> Menu {
+        Menu {
+            actions,
+            start_page: String::from("root"),
+            ignore_exit_status: false,
+            watch: None,
+            screen: Screen::Alternate,
+            picker: false,
+            history: None,
+        }
+    }
+
+    /// Start on the page with this name, instead of "root".
+    pub fn start_page<S: Into<String>>(mut self, name: S) -> Menu {
+        self.start_page = name.into();
+        self
+    }
+
+    /// Keep the menu open when a command fails, instead of returning an error.
+    pub fn ignore_exit_status(mut self, ignore: bool) -> Menu {
+        self.ignore_exit_status = ignore;
+        self
+    }
+
+    /// Load the action file again from this path whenever it changes, while the menu is open.
+    pub fn watch<P: Into<PathBuf>>(mut self, path: P) -> Menu {
+        self.watch = Some(path.into());
+        self
+    }
+
+    /// Show the menu in this many lines below the cursor, instead of on the whole screen.
+    pub fn inline(mut self, height: u16) -> Menu {
+        self.screen = Screen::Inline { height };
+        self
+    }
+
+    /// Return the entry that is picked instead of running its command. Entries without a command
+    /// that go to another page still do so, so that the user can look for the entry to pick.
+    pub fn picker(mut self, picker: bool) -> Menu {
+        self.picker = picker;
+        self
+    }
+
+    /// Record the entries that are run in this history, and use it to highlight the last used
+    /// entries of pages that have `highlight_last_used` set.
+    pub fn history(mut self, history: History) -> Menu {
+        self.history = Some(history);
+        self
+    }
+
+    /// Shows the menu until it is closed. Returns the entry that closed it, or None when the user
+    /// closed it with Esc.
+    ///
+    /// Entries that `exec` their command replace the whole process, and never return here.
+    pub fn run(self) -> Result<Option<Choice>, Error> {
+        if let Err(errors) = self.actions.validate(&self.start_page) {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(format_err!("Actions are invalid: {}", messages.join(" ")));
+        }
+
+        let result = run_menu(self);
+        // Errors are usually printed right after this, and must not end up on the alternate
+        // screen.
+        flush_terminal();
+        result
+    }
+}
+
+/// Loads and validates the action file again, for when it has changed on disk while the menu was
+/// open. Errors are returned as a single line to be shown in the status line.
+fn reload_actions(path: &PathBuf, start_page: &str) -> Result<ActionFile, String> {
+    let actions = ActionFile::load(path)
+        .map_err(|error| format!("Could not reload {}: {}", path.display(), error))?;
+
+    match actions.validate(start_page) {
+        Ok(()) => Ok(actions),
+        Err(errors) => {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            Err(format!("Reloaded file is invalid: {}", messages.join(" ")))
+        }
+    }
+}
+
+/// Watches the action file and the theme files it uses.
+fn watch_action_file(path: &Path, actions: &ActionFile) -> Result<Watcher, Error> {
+    let mut paths = vec![path.to_owned()];
+    paths.extend_from_slice(actions.theme_files());
+    Watcher::new(&paths)
+}
+
+fn flush_terminal() {
+    // Flush the output from Terminal being dropped; this is not done by termion itself.
+    // https://gitlab.redox-os.org/redox-os/termion/issues/158
+    //
+    // Printing to stderr before stdout is flushed, or letting other processes write to it,
+    // means that the text ends up on the alternate screen that will be removed as soon as *our*
+    // stdout buffer is flushed.
+    use std::io::Write;
+    ::std::io::stdout().flush().ok();
+}
+
+/// Wrapper around an AlternateScreen or inline terminal, that handles restoration on drop.
+struct TermHandle(Term, Screen);
+
+impl TermHandle {
+    /// Opens the terminal's "Alternate screen", or a region below the cursor for inline menus,
+    /// and hide the cursor.
+    ///
+    /// The alternate screen is like a separate screen that you can ouput to freely, and when this
+    /// screen is closed the previous screen is restored. Most terminal UIs use this in order to
+    /// not clobber output from earlier commands. For example, run vim and exit it again and you
+    /// can see that your terminal is restored to look like it did before you started vim.
+    ///
+    /// Will restore cursor when dropped, and erase the menu if it was inline.
+    fn new(screen: Screen) -> Result<TermHandle, Error> {
+        let backend = ScreenBackend::new(screen)?;
+        let mut terminal = Terminal::new(backend)?;
+        terminal.hide_cursor()?;
+        terminal.clear()?;
+        Ok(TermHandle(terminal, screen))
+    }
+
+    fn restart(self) -> Result<TermHandle, Error> {
+        let screen = self.1;
+        drop(self);
+        TermHandle::new(screen)
+    }
+}
+
+impl Drop for TermHandle {
+    fn drop(&mut self) {
+        self.0.show_cursor().ok();
+    }
+}
+
+/// Starts the main event loop.
+///
+/// Start:
+///     Begin on root page.
+///     Open alternate screen.
+///     Start watching the action file, if enabled.
+/// Loop:
+///     Render menu, or the help overlay when it was asked for.
+///     Wait for a valid input, or for the action file to change.
+///     Process input's event, possibly running a command or reloading the action file.
+///     Wait for user to press enter, if waiting is enabled.
+///     Update which page to be on.
+///     Exit if event tells us to.
+///     Repeat loop.
+/// End:
+///     Restore screen.
+///     Return the entry that closed the menu, if any.
+///
+fn run_menu(menu: Menu) -> Result<Option<Choice>, Error> {
+    // Code in this function is annotated according to the function documentation comment to help
+    // navigate it. It is quite big, sadly.
+
+    // Start
+    let Menu {
+        mut actions,
+        start_page,
+        ignore_exit_status,
+        watch,
+        screen,
+        picker,
+        mut history,
+    } = menu;
+    let error_on_failure = !ignore_exit_status;
+    // The page is tracked by name rather than by reference so the action file can be replaced
+    // when it is reloaded.
+    let mut current_page = start_page.clone();
+    let mut status = Status::new(&current_page);
+    // The entry that was run last, to highlight when coming back to its page.
+    let mut selected: Option<char> = None;
+    let mut showing_help = false;
+    // The entry that the repeat key runs again, with the name of its page. It starts out as the
+    // last entry in the history, so that it can be repeated after tydra was started again.
+    let mut repeat: Option<(String, Entry)> = history.as_ref().and_then(|history| {
+        let (entry, page_name) = *history.recent_entries(&actions).first()?;
+        Some((page_name.to_owned(), entry.clone()))
+    });
+    status.repeat = repeat.as_ref().map(|(_, entry)| entry.title().to_owned());
+
+    let watcher = match watch {
+        Some(ref path) => Some(watch_action_file(path, &actions)?),
+        None => None,
+    };
+    let mut events = Events::new(watcher)?;
+
+    let mut terminal = TermHandle::new(screen)?;
+
+    // Loop
+    loop {
+        let virtual_page = if is_virtual_page(&current_page) {
+            Some(VirtualPage::new(&current_page, &actions, history.as_ref()))
+        } else {
+            None
+        };
+        let page = match virtual_page {
+            Some(ref virtual_page) => &virtual_page.page,
+            None => actions.get_page(&current_page),
+        };
+        let page_settings = actions.settings_accumulator().with_page(page);
+        if showing_help {
+            render_help(&mut terminal.0, page, &page_settings, &status)?;
+        } else {
+            let highlighted = selected.or_else(|| match history {
+                Some(ref history) if page_settings.highlight_last_used => {
+                    history.last_used(page, &current_page)
+                }
+                _ => None,
+            });
+            render(&mut terminal.0, page, &page_settings, highlighted, &status)?;
+        }
+
+        // Wait for an event from user input. Any key closes the help overlay, while resizing
+        // the terminal only redraws it.
+        let (action, mut shortcut) = if showing_help {
+            match process_help_input(&mut events)? {
+                Some(Action::Resize) => (Action::Resize, None),
+                Some(action) => {
+                    showing_help = false;
+                    (action, None)
+                }
+                None => {
+                    showing_help = false;
+                    continue;
+                }
+            }
+        } else {
+            process_input(page, &mut events)?
+        };
+        if !matches!(action, Action::Resize) {
+            status.clear_info();
+        }
+        // Entries on pages made from the history act like they were picked on their own page.
+        let original = virtual_page
+            .as_ref()
+            .and_then(|virtual_page| virtual_page.original(shortcut?));
+        if let Some((page_name, original_shortcut)) = original {
+            status.go_to_page(page_name);
+            current_page = page_name.to_owned();
+            shortcut = Some(original_shortcut);
+        }
+        let mut entry = shortcut.and_then(|shortcut| {
+            let page = actions.get_page(&current_page);
+            page.entry_with_shortcut(shortcut).cloned()
+        });
+        let mut entry_page = current_page.clone();
+        // The repeat key runs the last entry again like it was picked on its own page, except
+        // that returning to the same page stays on the current one.
+        let action = match (action, repeat.as_ref()) {
+            (Action::Repeat, Some((page_name, last_entry))) => {
+                entry = Some(last_entry.clone());
+                entry_page = page_name.clone();
+                Action::from(last_entry)
+            }
+            (Action::Repeat, None) => {
+                status.info("Nothing has been run yet.");
+                continue;
+            }
+            (action, _) => action,
+        };
+        if let Some(entry) = entry.as_ref().filter(|entry| picker && is_choice(entry)) {
+            return Ok(Some(Choice {
+                page: entry_page,
+                entry: entry.clone(),
+            }));
+        }
+        if let Some(entry) = entry.as_ref().filter(|entry| does_something(entry)) {
+            repeat = Some((entry_page.clone(), entry.clone()));
+            status.repeat = Some(entry.title().to_owned());
+        }
+        let return_to = match action {
+            // Quit / Exit.
+            Action::Exit => Return::Quit,
+
+            // Redraw menu.
+            Action::Redraw => {
+                terminal = terminal.restart()?;
+                Return::SamePage
+            }
+
+            Action::Pause => {
+                terminal = pause_tydra(terminal)?;
+                Return::SamePage
+            }
+
+            // Make the buffers match the new size. The layout is picked again when rendering.
+            Action::Resize => {
+                let size = terminal.0.size()?;
+                terminal.0.resize(size)?;
+                Return::SamePage
+            }
+
+            Action::Help => {
+                showing_help = true;
+                Return::SamePage
+            }
+
+            Action::Repeat => unreachable!("The repeat key is replaced by the action to repeat"),
+
+            // Replace the action file with the new version from disk. Stay on the current page if
+            // it still exists. If the new file is broken, keep using the old one and show the
+            // problem in the status line instead.
+            Action::Reload => match reload_actions(watch.as_ref().unwrap(), &start_page) {
+                Ok(new_actions) => {
+                    // The new file can use other theme files than before.
+                    match watch_action_file(watch.as_ref().unwrap(), &new_actions) {
+                        Ok(watcher) => {
+                            events.set_watcher(Some(watcher));
+                            status.message = None;
+                        }
+                        Err(error) => {
+                            status.error(format!("Could not watch the theme files: {}", error))
+                        }
+                    }
+                    actions = new_actions;
+                    if actions.has_page(&current_page) || is_virtual_page(&current_page) {
+                        Return::SamePage
+                    } else {
+                        Return::OtherPage(start_page.clone())
+                    }
+                }
+                Err(message) => {
+                    status.error(message);
+                    Return::SamePage
+                }
+            },
+
+            // Run a command in normal mode, e.g. pause tydra and run the command. Return to tydra
+            // after the command exits.
+            Action::Run {
+                command,
+                return_to,
+                wait,
+            } => {
+                let (new_terminal, result) =
+                    run_normal(terminal, error_on_failure, command, wait, |result| {
+                        record_run(&mut history, &mut status, &entry_page, &entry, Some(result))
+                    })?;
+                terminal = new_terminal;
+                status.last_run = result.or(status.last_run);
+                return_to
+            }
+
+            // Replace tydra with the command's process.
+            // If it returns, it has to be an error.
+            Action::RunExec { command } => {
+                record_run(&mut history, &mut status, &entry_page, &entry, None);
+                return Err(run_exec(terminal, command));
+            }
+
+            // Run command in background and immediately return to the menu again.
+            Action::RunBackground { command, return_to } => {
+                unsafe { runner::run_background(&command)? };
+                status.info(format!("Started in the background: {}", command));
+                record_run(&mut history, &mut status, &entry_page, &entry, None);
+                return_to
+            }
+
+            Action::Copy { text, return_to } => {
+                match clipboard::copy(&text) {
+                    Ok(()) => status.info("Copied to the clipboard"),
+                    Err(error) => status.error(error.to_string()),
+                }
+                record_run(&mut history, &mut status, &entry_page, &entry, None);
+                return_to
+            }
+
+            // The text is printed by whoever runs the menu, as the menu has to be closed first.
+            Action::Type { .. } => {
+                record_run(&mut history, &mut status, &entry_page, &entry, None);
+                Return::Quit
+            }
+        };
+
+        // Decide on which page to render now.
+        match return_to {
+            Return::Quit => {
+                return Ok(entry.map(|entry| Choice {
+                    page: entry_page,
+                    entry,
+                }))
+            }
+            Return::SamePage => selected = shortcut.or(selected),
+            Return::OtherPage(page_name) => {
+                status.go_to_page(&page_name);
+                current_page = page_name;
+                selected = None;
+            }
+        }
+    }
+}
+
+/// Tells if picking the entry is a choice in picker mode, rather than only a way to get to
+/// another page.
+fn is_choice(entry: &Entry) -> bool {
+    let goes_to_page = matches!(entry.return_to(), Return::OtherPage(_));
+    !(goes_to_page && *entry.command() == Command::None)
+}
+
+/// Tells if picking the entry runs, copies or types something, rather than only going to another
+/// page or closing the menu.
+fn does_something(entry: &Entry) -> bool {
+    let uses_snippet = matches!(entry.runner_mode(), RunMode::Copy | RunMode::Type);
+    uses_snippet || *entry.command() != Command::None
+}
+
+/// Adds a run of the entry to the history, if there is one. Problems with the history file are
+/// shown in the status line rather than closing the menu.
+fn record_run(
+    history: &mut Option<History>,
+    status: &mut Status,
+    page: &str,
+    entry: &Option<Entry>,
+    result: Option<&RunResult>,
+) {
+    if let (Some(history), Some(entry)) = (history.as_mut(), entry.as_ref()) {
+        if let Err(error) = history.record(page, entry, result) {
+            status.error(format!("Could not save the history: {}", error));
+        }
+    }
+}
+
+/// Runs the command on the normal screen, and gives the result to `finished` before anything
+/// else happens, even when the command failed.
+fn run_normal<F>(
+    terminal: TermHandle,
+    error_on_failure: bool,
+    command: Command,
+    wait: bool,
+    finished: F,
+) -> Result<(TermHandle, Option<RunResult>), Error>
+where
+    F: FnOnce(&RunResult),
+{
+    // Run commands on the normal screen. This preserves the command's output even
+    // after tydra exits.
+    let screen = terminal.1;
+    drop(terminal);
+    flush_terminal();
+
+    let started = Instant::now();
+    let result = match runner::run_normal(&command) {
+        Some(Ok(exit_status)) => {
+            let result = RunResult {
+                exit_status,
+                duration: started.elapsed(),
+            };
+            finished(&result);
+            if error_on_failure && !exit_status.success() {
+                return Err(format_err!(
+                    "Command exited with exit status {}: {}",
+                    exit_status.code().unwrap_or(1),
+                    command
+                ));
+            }
+            Some(result)
+        }
+        Some(Err(err)) => return Err(err),
+        None => None,
+    };
+
+    if wait {
+        wait_for_confirmation()?;
+    }
+
+    Ok((TermHandle::new(screen)?, result))
+}
+
+// Can use `!` when it is stable; it never returns a non-error
+fn run_exec(terminal: TermHandle, command: Command) -> Error {
+    // Restore screen for the new command.
+    drop(terminal);
+    flush_terminal();
+
+    // If this returns, then it failed to exec the process so wrap that value in a
+    // error.
+    runner::run_exec(&command)
+}
+
+fn pause_tydra(terminal: TermHandle) -> Result<TermHandle, Error> {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    let screen = terminal.1;
+    drop(terminal);
+    flush_terminal();
+
+    // Tell this process to pause (standard ^Z signal)
+    kill(Pid::this(), Signal::SIGTSTP)?;
+
+    // Now the process is running again. Restore the terminal!
+    TermHandle::new(screen)
+}
+
+/// Reads input events until a valid event is found and returns it as an Action, along with the
+/// shortcut of the entry it came from. Reads actions from provided page to determine what events
+/// are valid.
+fn process_input(page: &Page, events: &mut Events) -> Result<(Action, Option<char>), Error> {
+    // Iterate all valid events
+    loop {
+        match events.next()? {
+            Event::FileChanged => return Ok((Action::Reload, None)),
+            Event::Resized => return Ok((Action::Resize, None)),
+            Event::Key(event::Key::Esc) => return Ok((Action::Exit, None)),
+            Event::Key(event::Key::Ctrl('l')) => return Ok((Action::Redraw, None)),
+            Event::Key(event::Key::Ctrl('z')) => return Ok((Action::Pause, None)),
+            Event::Key(event::Key::Char(chr)) => {
+                if let Some(entry) = page.entry_with_shortcut(chr) {
+                    return Ok((entry.into(), Some(chr)));
+                }
+                // Entries can use the help and repeat keys as their shortcuts, so they are only
+                // handled here.
+                if chr == HELP_KEY {
+                    return Ok((Action::Help, None));
+                }
+                if chr == REPEAT_KEY {
+                    return Ok((Action::Repeat, None));
+                }
+            }
+            Event::Key(_) => {}
+        }
+    }
+}
+
+/// Waits for a key press to close the help overlay, and returns the action for it. Most keys only
+/// close the help, and return no action.
+fn process_help_input(events: &mut Events) -> Result<Option<Action>, Error> {
+    match events.next()? {
+        Event::FileChanged => Ok(Some(Action::Reload)),
+        Event::Resized => Ok(Some(Action::Resize)),
+        Event::Key(event::Key::Ctrl('z')) => Ok(Some(Action::Pause)),
+        Event::Key(_) => Ok(None),
+    }
+}
+
+/// Waits for the user to press Enter (or Escape, just to be nice) before returning.
+fn wait_for_confirmation() -> Result<(), Error> {
+    use termion::input::TermRead;
+
+    println!("Press enter to continue... ");
+
+    for event in RawStdin.keys().flat_map(Result::ok) {
+        match event {
+            event::Key::Char('\n') | event::Key::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+
+    Err(format_err!("stdin was closed."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{Group, Page};
+
+    #[test]
+    fn it_does_not_run_invalid_menus() {
+        let actions = ActionFile::new().with_page(
+            "root",
+            Page::new("Start").with_group(Group::new().with_entry(Entry::new('a', "A"))),
+        );
+
+        let error = Menu::new(actions).start_page("missing").run().unwrap_err();
+        assert!(error.to_string().starts_with("Actions are invalid:"));
+    }
+}
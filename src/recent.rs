@@ -0,0 +1,133 @@
+//! Pages that are made from the history instead of being in the action file. They can be used
+//! as the start page or as the target of `return`, like any other page.
+
+use crate::actions::{ActionFile, Group, Page};
+use crate::history::History;
+
+/// The entries that were used most recently.
+pub const RECENT_PAGE: &str = "@recent";
+
+/// The entries that were used most often.
+pub const FREQUENT_PAGE: &str = "@frequent";
+
+/// The entries get the shortcuts 1 to 9, so there is room for nine of them.
+const SHORTCUTS: &str = "123456789";
+
+/// Tells if the page is made from the history. Page names in action files cannot start with @,
+/// so they never hide these.
+pub fn is_virtual_page(page_name: &str) -> bool {
+    page_name == RECENT_PAGE || page_name == FREQUENT_PAGE
+}
+
+/// A page with entries from other pages of the action file, under new shortcuts.
+pub struct VirtualPage {
+    pub page: Page,
+    /// The page name and shortcut of each entry on its own page, in the order of the entries.
+    originals: Vec<(String, char)>,
+}
+
+impl VirtualPage {
+    /// Makes the page with the given name from the history. Without a history, the page has no
+    /// entries.
+    pub fn new(page_name: &str, actions: &ActionFile, history: Option<&History>) -> VirtualPage {
+        let (title, entries) = match (page_name, history) {
+            (FREQUENT_PAGE, Some(history)) => {
+                ("Frequently used", history.frequent_entries(actions))
+            }
+            (FREQUENT_PAGE, None) => ("Frequently used", Vec::new()),
+            (_, Some(history)) => ("Recently used", history.recent_entries(actions)),
+            (_, None) => ("Recently used", Vec::new()),
+        };
+
+        let mut group = Group::new();
+        let mut originals = Vec::new();
+        for ((entry, original_page), shortcut) in entries.into_iter().zip(SHORTCUTS.chars()) {
+            group = group.with_entry(entry.clone().with_shortcut(shortcut));
+            originals.push((original_page.to_owned(), entry.shortcut()));
+        }
+
+        let page = if originals.is_empty() {
+            Page::new(title).with_header("Nothing has been run yet.")
+        } else {
+            Page::new(title).with_group(group)
+        };
+        VirtualPage { page, originals }
+    }
+
+    /// The page name and shortcut of the entry that has the shortcut on this page.
+    pub fn original(&self, shortcut: char) -> Option<(&str, char)> {
+        let index = SHORTCUTS.chars().position(|other| other == shortcut)?;
+        self.originals
+            .get(index)
+            .map(|(page_name, shortcut)| (page_name.as_str(), *shortcut))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Entry;
+    use crate::status::RunResult;
+    use crate::testing::TestDirectory;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+    use std::time::Duration;
+
+    #[test]
+    fn it_lists_recent_and_frequent_entries() {
+        let actions: ActionFile = serde_yaml::from_str(
+            r#"
+pages:
+  root:
+    groups:
+      - entries:
+          - {shortcut: a, title: Apple, command: "true"}
+          - {shortcut: b, title: Banana, command: "true", id: banana}
+  other:
+    groups:
+      - entries:
+          - {shortcut: a, title: Avocado, command: "true"}"#,
+        )
+        .unwrap();
+        let directory = TestDirectory::new("recent");
+        let mut history = History::open(directory.join("history.jsonl"), "recent.yml").unwrap();
+        let result = RunResult {
+            exit_status: ExitStatus::from_raw(0),
+            duration: Duration::from_millis(10),
+        };
+        let mut run = |page: &str, shortcut: char| {
+            let entry = actions
+                .get_page(page)
+                .entry_with_shortcut(shortcut)
+                .unwrap();
+            history.record(page, entry, Some(&result)).unwrap();
+        };
+        run("root", 'b');
+        run("root", 'b');
+        run("other", 'a');
+        run("root", 'a');
+        // Entries that have been removed from the action file are skipped.
+        history
+            .record("root", &Entry::new('z', "Removed"), None)
+            .unwrap();
+
+        let titles = |page: &VirtualPage| -> Vec<String> {
+            page.page
+                .all_entries()
+                .map(|entry| format!("{} {}", entry.shortcut(), entry.title()))
+                .collect()
+        };
+
+        let recent = VirtualPage::new(RECENT_PAGE, &actions, Some(&history));
+        assert_eq!(titles(&recent), ["1 Apple", "2 Avocado", "3 Banana"]);
+        assert_eq!(recent.original('2'), Some(("other", 'a')));
+        assert_eq!(recent.original('4'), None);
+
+        let frequent = VirtualPage::new(FREQUENT_PAGE, &actions, Some(&history));
+        assert_eq!(titles(&frequent), ["1 Banana", "2 Apple", "3 Avocado"]);
+        assert_eq!(frequent.original('1'), Some(("root", 'b')));
+
+        let empty = VirtualPage::new(RECENT_PAGE, &actions, None);
+        assert_eq!(empty.page.all_entries().count(), 0);
+    }
+}
@@ -60,7 +60,8 @@ pub unsafe fn run_background(command: &Command) -> Result<(), Error> {
                 // parent quits.
                 nix::unistd::setsid().ok();
                 Ok(())
-            }).spawn()
+            })
+            .spawn()
             .map_err(|e| e.into())
             .map(|_| ()),
         None => Ok(()),
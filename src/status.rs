@@ -0,0 +1,161 @@
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+/// What the status line at the bottom of the menu shows.
+#[derive(Debug, Default)]
+pub struct Status {
+    /// Names of the pages that led to the current one, ending with the current page.
+    pub page_path: Vec<String>,
+
+    /// How the last command that returned to the menu went.
+    pub last_run: Option<RunResult>,
+
+    /// Title of the entry that the repeat key runs again.
+    pub repeat: Option<String>,
+
+    /// A message for the user, like a problem with the action file.
+    pub message: Option<Message>,
+}
+
+/// The outcome of running a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunResult {
+    pub exit_status: ExitStatus,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub text: String,
+
+    /// Errors stay until they are replaced, while other messages are only shown until the next
+    /// key press.
+    pub is_error: bool,
+}
+
+impl Status {
+    pub fn new(start_page: &str) -> Status {
+        Status {
+            page_path: vec![start_page.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    /// Records that the menu went to another page. Going back to a page that is already in the
+    /// path removes the pages after it, so that menus that link back and forth do not make the
+    /// path grow forever.
+    pub fn go_to_page(&mut self, page_name: &str) {
+        match self.page_path.iter().position(|name| name == page_name) {
+            Some(index) => self.page_path.truncate(index + 1),
+            None => self.page_path.push(page_name.to_owned()),
+        }
+    }
+
+    pub fn info<S: Into<String>>(&mut self, text: S) {
+        self.message = Some(Message {
+            text: text.into(),
+            is_error: false,
+        });
+    }
+
+    pub fn error<S: Into<String>>(&mut self, text: S) {
+        self.message = Some(Message {
+            text: text.into(),
+            is_error: true,
+        });
+    }
+
+    /// Removes the message, unless it is an error.
+    pub fn clear_info(&mut self) {
+        if self
+            .message
+            .as_ref()
+            .is_some_and(|message| !message.is_error)
+        {
+            self.message = None;
+        }
+    }
+}
+
+impl RunResult {
+    pub fn success(&self) -> bool {
+        self.exit_status.success()
+    }
+}
+
+impl fmt::Display for RunResult {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let duration = format_duration(self.duration);
+        match (self.exit_status.code(), self.exit_status.signal()) {
+            (Some(0), _) => write!(formatter, "Succeeded in {}", duration),
+            (Some(code), _) => write!(formatter, "Exited with {} after {}", code, duration),
+            (None, Some(signal)) => {
+                write!(formatter, "Killed by signal {} after {}", signal, duration)
+            }
+            (None, None) => write!(formatter, "Stopped after {}", duration),
+        }
+    }
+}
+
+/// Formats a duration as short as makes sense for how long it is, like "340ms", "2.5s" or "3m 5s".
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else if seconds >= 1 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tracks_the_page_path() {
+        let mut status = Status::new("root");
+        status.go_to_page("git");
+        status.go_to_page("branches");
+        assert_eq!(status.page_path, vec!["root", "git", "branches"]);
+
+        status.go_to_page("git");
+        assert_eq!(status.page_path, vec!["root", "git"]);
+        status.go_to_page("root");
+        assert_eq!(status.page_path, vec!["root"]);
+    }
+
+    #[test]
+    fn it_describes_run_results() {
+        let result = |code: i32, millis: u64| RunResult {
+            exit_status: ExitStatus::from_raw(code),
+            duration: Duration::from_millis(millis),
+        };
+
+        assert_eq!(result(0, 340).to_string(), "Succeeded in 340ms");
+        // Raw wait statuses keep the exit code in the second byte.
+        assert_eq!(
+            result(2 << 8, 2_540).to_string(),
+            "Exited with 2 after 2.5s"
+        );
+        assert_eq!(
+            result(9, 185_000).to_string(),
+            "Killed by signal 9 after 3m 5s"
+        );
+    }
+
+    #[test]
+    fn it_only_clears_info_messages() {
+        let mut status = Status::new("root");
+        status.info("Started in the background");
+        status.clear_info();
+        assert_eq!(status.message, None);
+
+        status.error("Broken file");
+        status.clear_info();
+        assert!(status.message.is_some());
+    }
+}
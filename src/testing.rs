@@ -0,0 +1,37 @@
+//! Helpers that the unit tests of several modules share.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An empty directory for a test to keep its files in. It is removed again when it is dropped,
+/// even when the test fails.
+pub struct TestDirectory {
+    path: PathBuf,
+}
+
+impl TestDirectory {
+    /// Makes the directory in the temporary directory of the system. Tests run at the same time,
+    /// so each of them needs a name of its own.
+    pub fn new(name: &str) -> TestDirectory {
+        let path = std::env::temp_dir().join(format!("tydra-{}-{}", name, std::process::id()));
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        fs::create_dir_all(&path).unwrap();
+        TestDirectory { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.path).ok();
+    }
+}
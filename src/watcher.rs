@@ -0,0 +1,115 @@
+use failure::Error;
+use inotify::{Inotify, WatchMask};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+
+/// Watches a set of files for changes using inotify.
+///
+/// The directories containing the files are watched rather than the files themselves. Most editors
+/// save by writing a new file and renaming it over the old one, which would make a watch on the
+/// original file silently stop reporting changes.
+pub struct Watcher {
+    inotify: Inotify,
+    file_names: HashSet<OsString>,
+    buffer: Vec<u8>,
+}
+
+impl Watcher {
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> Result<Watcher, Error> {
+        let mut inotify = Inotify::init()?;
+        let mut file_names = HashSet::new();
+        let mut directories = HashSet::new();
+
+        for path in paths {
+            let path = path.as_ref();
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| format_err!("Cannot watch {}; it is not a file", path.display()))?;
+            file_names.insert(file_name.to_owned());
+            directories.insert(parent_directory(path));
+        }
+
+        for directory in directories {
+            inotify.add_watch(
+                &directory,
+                WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE,
+            )?;
+        }
+
+        Ok(Watcher {
+            inotify,
+            file_names,
+            buffer: vec![0; 4096],
+        })
+    }
+
+    /// Consumes all pending events and returns true if any of them concerned one of the watched
+    /// files. Never blocks.
+    pub fn has_changed(&mut self) -> Result<bool, Error> {
+        let mut changed = false;
+        loop {
+            let events = match self.inotify.read_events(&mut self.buffer) {
+                Ok(events) => events,
+                Err(ref error) if error.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            let mut empty = true;
+            for event in events {
+                empty = false;
+                if let Some(name) = event.name {
+                    changed |= self.file_names.contains(name);
+                }
+            }
+
+            if empty {
+                break;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+}
+
+fn parent_directory(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if parent != Path::new("") => parent.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestDirectory;
+    use std::fs;
+
+    #[test]
+    fn it_detects_changes_to_watched_files_only() {
+        let directory = TestDirectory::new("watcher");
+        let watched = directory.join("actions.yml");
+        let other = directory.join("other.yml");
+        fs::write(&watched, "pages: {}").unwrap();
+
+        let mut watcher = Watcher::new(&[&watched]).unwrap();
+        assert!(!watcher.has_changed().unwrap());
+
+        fs::write(&other, "pages: {}").unwrap();
+        assert!(!watcher.has_changed().unwrap());
+
+        fs::write(&watched, "pages: {}").unwrap();
+        assert!(watcher.has_changed().unwrap());
+        assert!(!watcher.has_changed().unwrap());
+
+        // Editors usually save by renaming a new file over the old one.
+        fs::rename(&other, &watched).unwrap();
+        assert!(watcher.has_changed().unwrap());
+    }
+}
@@ -0,0 +1,118 @@
+use failure::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The shells that tydra can make a widget for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WidgetShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl WidgetShell {
+    pub fn variants() -> [&'static str; 3] {
+        ["bash", "zsh", "fish"]
+    }
+}
+
+impl FromStr for WidgetShell {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<WidgetShell, Error> {
+        match string {
+            "bash" => Ok(WidgetShell::Bash),
+            "zsh" => Ok(WidgetShell::Zsh),
+            "fish" => Ok(WidgetShell::Fish),
+            other => Err(format_err!("Unknown shell: {}", other)),
+        }
+    }
+}
+
+/// Makes a shell function that shows the menu below the prompt when pressing Alt-T, and inserts
+/// the command of the picked entry into the command line at the cursor instead of running it.
+///
+/// The menu is read from the action file, or from `$TYDRA_ACTION_FILE` when the widget runs if
+/// there is no action file.
+pub fn shell_widget(shell: WidgetShell, action_file: Option<&Path>) -> String {
+    let action_file = match action_file {
+        Some(path) => quote(shell, &path.to_string_lossy()),
+        None => String::from("\"$TYDRA_ACTION_FILE\""),
+    };
+    let tydra = format!(
+        "tydra --inline --print-choice --choice-format command {}",
+        action_file
+    );
+
+    match shell {
+        WidgetShell::Bash => format!(
+            r#"# Add this to ~/.bashrc:
+#   eval "$(tydra --shell-widget bash ACTION_FILE)"
+__tydra_widget() {{
+    local choice
+    choice=$({tydra}) || return
+    READLINE_LINE="${{READLINE_LINE:0:READLINE_POINT}}$choice${{READLINE_LINE:READLINE_POINT}}"
+    READLINE_POINT=$((READLINE_POINT + ${{#choice}}))
+}}
+bind -x '"\et": __tydra_widget'
+"#,
+            tydra = tydra
+        ),
+        WidgetShell::Zsh => format!(
+            r#"# Add this to ~/.zshrc:
+#   eval "$(tydra --shell-widget zsh ACTION_FILE)"
+tydra-widget() {{
+    local choice
+    choice=$({tydra}) && LBUFFER+=$choice
+    zle reset-prompt
+}}
+zle -N tydra-widget
+bindkey '\et' tydra-widget
+"#,
+            tydra = tydra
+        ),
+        WidgetShell::Fish => format!(
+            r#"# Add this to ~/.config/fish/config.fish:
+#   tydra --shell-widget fish ACTION_FILE | source
+function __tydra_widget
+    set -l choice ({tydra} | string collect)
+    and commandline --insert -- $choice
+    commandline --function repaint
+end
+bind \et __tydra_widget
+"#,
+            tydra = tydra
+        ),
+    }
+}
+
+/// Quotes the text as a single word for the shell.
+fn quote(shell: WidgetShell, text: &str) -> String {
+    match shell {
+        WidgetShell::Bash | WidgetShell::Zsh => format!("'{}'", text.replace('\'', r"'\''")),
+        WidgetShell::Fish => format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_runs_tydra_with_the_action_file() {
+        let path = Path::new("/home/me/it's actions.yml");
+
+        let bash = shell_widget(WidgetShell::Bash, Some(path));
+        assert!(bash.contains(
+            "choice=$(tydra --inline --print-choice --choice-format command \
+             '/home/me/it'\\''s actions.yml') || return"
+        ));
+        assert!(bash.contains("bind -x '\"\\et\": __tydra_widget'"));
+
+        let fish = shell_widget(WidgetShell::Fish, Some(path));
+        assert!(fish.contains("--choice-format command '/home/me/it\\'s actions.yml' |"));
+
+        let zsh = shell_widget(WidgetShell::Zsh, None);
+        assert!(zsh.contains("command \"$TYDRA_ACTION_FILE\") && LBUFFER+=$choice"));
+    }
+}
@@ -0,0 +1,446 @@
+mod support;
+
+use crate::support::{fixture, test_directory, tydra_command, Tydra};
+
+#[test]
+fn it_shows_the_start_page_and_exits_on_escape() {
+    let directory = test_directory("exits_on_escape");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+
+    tydra.wait_for_text("End to end");
+    assert!(tydra.screen().contains("[t] Touch a file"));
+    assert!(tydra.is_alternate_screen());
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_runs_commands_and_returns_to_the_menu() {
+    let directory = test_directory("returns_to_the_menu");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    tydra.send("t");
+    tydra.wait_until("the file to be touched", |_| {
+        directory.join("touched").exists()
+    });
+    tydra.wait_until("the menu to come back", |tydra| {
+        tydra.is_alternate_screen() && tydra.screen().contains("End to end")
+    });
+
+    tydra.send("q");
+    assert!(tydra.wait_for_exit().success());
+    assert!(directory.join("quit").exists());
+}
+
+#[test]
+fn it_goes_to_the_page_that_entries_return_to() {
+    let directory = test_directory("returns_to_pages");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    tydra.send("o");
+    tydra.wait_for_text("Another page");
+    // Shortcuts of the start page do nothing here.
+    tydra.send("t");
+    tydra.send("b");
+    tydra.wait_for_text("End to end");
+    assert!(directory.join("back").exists());
+    assert!(!directory.join("touched").exists());
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_exits_with_the_status_of_exec_commands() {
+    let directory = test_directory("exec_status");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    tydra.send("x");
+    assert_eq!(tydra.wait_for_exit().code(), Some(3));
+}
+
+#[test]
+fn it_stops_when_commands_fail_unless_told_not_to() {
+    let directory = test_directory("failing_commands");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+    tydra.send("f");
+    tydra.wait_for_text("Error: Command exited with exit status 2: exit 2");
+    tydra.wait_for_exit();
+
+    let mut tydra = Tydra::spawn(&["-e", &fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+    tydra.send("f");
+    tydra.send("t");
+    tydra.wait_until("the file to be touched", |_| {
+        directory.join("touched").exists()
+    });
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_waits_for_enter_after_commands_in_wait_mode() {
+    let directory = test_directory("wait_mode");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    tydra.send("w");
+    tydra.wait_for_text("Press enter to continue");
+    assert!(tydra.screen().contains("Some output"));
+    assert!(!tydra.is_alternate_screen());
+
+    tydra.send("\r");
+    tydra.wait_until("the menu to come back", |tydra| tydra.is_alternate_screen());
+    tydra.wait_for_text("End to end");
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_shows_the_menu_again_after_being_suspended() {
+    let directory = test_directory("suspend");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    // Ctrl-Z
+    tydra.send("\x1a");
+    tydra.wait_for_suspend_and_continue();
+    tydra.wait_until("the menu to come back", |tydra| {
+        tydra.is_alternate_screen() && tydra.screen().contains("End to end")
+    });
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_redraws_the_menu_when_the_terminal_is_resized() {
+    let directory = test_directory("resize");
+    let mut tydra = Tydra::spawn_with_size(&[&fixture("end_to_end.yml")], &directory, 80, 24);
+    tydra.wait_for_text("[w] Wait after running  [f] Fail");
+
+    // The entries wrap to fit in the new width.
+    tydra.resize(40, 24);
+    tydra.wait_for_text("\n[f] Fail");
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_shows_inline_menus_below_the_cursor() {
+    let directory = test_directory("inline");
+    let mut tydra = Tydra::spawn(
+        &["--inline", "--height", "8", &fixture("end_to_end.yml")],
+        &directory,
+    );
+    tydra.wait_for_text("End to end");
+    assert!(!tydra.is_alternate_screen());
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+    // The menu is erased again.
+    assert!(!tydra.screen().contains("End to end"));
+}
+
+#[test]
+fn it_prints_the_picked_entry_instead_of_running_it() {
+    let directory = test_directory("print_choice");
+    let (mut tydra, mut output) = Tydra::spawn_capturing_stdout(
+        &[
+            "--print-choice",
+            "--page",
+            "picker",
+            &fixture("end_to_end.yml"),
+        ],
+        &directory,
+    );
+    tydra.wait_for_text("Pick a color");
+    tydra.send("r");
+    assert!(tydra.wait_for_exit().success());
+    assert_eq!(read_to_end(&mut output), "#ff0000\n");
+
+    let (mut tydra, mut output) = Tydra::spawn_capturing_stdout(
+        &[
+            "--print-choice",
+            "--choice-format",
+            "id",
+            "--page",
+            "picker",
+            &fixture("end_to_end.yml"),
+        ],
+        &directory,
+    );
+    tydra.wait_for_text("Pick a color");
+    tydra.send("g");
+    assert!(tydra.wait_for_exit().success());
+    assert_eq!(read_to_end(&mut output), "green\n");
+    assert!(!directory.join("green").exists());
+}
+
+#[test]
+fn it_goes_to_other_pages_while_picking() {
+    let directory = test_directory("print_choice_pages");
+    let mut tydra = Tydra::spawn(
+        &[
+            "--print-choice",
+            "--choice-format",
+            "json",
+            "--page",
+            "picker",
+            &fixture("end_to_end.yml"),
+        ],
+        &directory,
+    );
+    tydra.wait_for_text("Pick a color");
+    tydra.send("m");
+    tydra.wait_for_text("More colors");
+    tydra.send("b");
+    assert!(tydra.wait_for_exit().success());
+    tydra.wait_for_text(
+        r##"{"page":"more","shortcut":"b","id":null,"title":"Blue","value":"#0000ff"}"##,
+    );
+}
+
+#[test]
+fn it_exits_with_1_when_nothing_is_picked() {
+    let directory = test_directory("print_choice_cancel");
+    let (mut tydra, mut output) = Tydra::spawn_capturing_stdout(
+        &[
+            "--print-choice",
+            "--page",
+            "picker",
+            &fixture("end_to_end.yml"),
+        ],
+        &directory,
+    );
+    tydra.wait_for_text("Pick a color");
+    tydra.send("\x1b");
+    assert_eq!(tydra.wait_for_exit().code(), Some(1));
+    assert_eq!(read_to_end(&mut output), "");
+}
+
+#[test]
+fn it_exits_with_2_when_the_action_file_cannot_be_loaded_for_picking() {
+    let directory = test_directory("print_choice_missing_file");
+    let missing = directory.join("missing.yml");
+    let output = tydra_command(&["--print-choice", missing.to_str().unwrap()], &directory)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+fn read_to_end(output: &mut std::fs::File) -> String {
+    use std::io::Read;
+    let mut text = String::new();
+    output.read_to_string(&mut text).unwrap();
+    text
+}
+
+#[test]
+fn it_shows_menus_given_as_arguments() {
+    let directory = test_directory("entry_arguments");
+    let mut tydra = Tydra::spawn(
+        &[
+            "--title",
+            "Proceed?",
+            "--entry",
+            "y:Yes:exit 4",
+            "--entry",
+            "n:No",
+        ],
+        &directory,
+    );
+    tydra.wait_for_text("Proceed?");
+    assert!(tydra.screen().contains("[y] Yes  [n] No"));
+
+    tydra.send("y");
+    assert_eq!(tydra.wait_for_exit().code(), Some(4));
+}
+
+#[test]
+fn it_runs_single_entries_without_the_menu() {
+    let directory = test_directory("run_command");
+    let output = tydra_command(&["run", "other/b"], &directory)
+        .env("TYDRA_ACTION_FILE", fixture("end_to_end.yml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(directory.join("back").exists());
+
+    let status = tydra_command(&["run", "root/f", &fixture("end_to_end.yml")], &directory)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+}
+
+#[test]
+fn it_fails_to_run_entries_when_the_action_file_cannot_be_loaded() {
+    let directory = test_directory("run_missing_file");
+    let missing = directory.join("missing.yml");
+    let missing = missing.to_str().unwrap();
+
+    let output = tydra_command(&["run", "root/t", missing], &directory)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error while loading"));
+
+    let status = tydra_command(&[missing, "--run", "root:t"], &directory)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn it_fails_to_print_the_graph_when_the_action_file_cannot_be_loaded() {
+    let directory = test_directory("graph_missing_file");
+    let missing = directory.join("missing.yml");
+    let output = tydra_command(&["graph", missing.to_str().unwrap()], &directory)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+#[test]
+fn it_lists_the_entries_that_were_run_in_the_history() {
+    let directory = test_directory("history");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    tydra.send("t");
+    tydra.wait_until("the menu to come back", |tydra| {
+        directory.join("touched").exists() && tydra.screen().contains("End to end")
+    });
+    tydra.send("f");
+    tydra.wait_for_exit();
+
+    let output = tydra_command(&["history"], &directory).output().unwrap();
+    assert!(output.status.success());
+    let lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    assert!(lines[0].ends_with("tests/fixtures/end_to_end.yml"));
+    assert!(lines[1].starts_with("just now root:f Fail Exited with 2 after"));
+    assert!(lines[2].starts_with("just now root:t Touch a file Succeeded in"));
+    assert_eq!(lines.len(), 3);
+}
+
+#[test]
+fn it_shows_recently_used_entries_on_the_recent_page() {
+    let directory = test_directory("recent_page");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+    tydra.send("t");
+    tydra.wait_until("the menu to come back", |tydra| {
+        directory.join("touched").exists() && tydra.screen().contains("End to end")
+    });
+    tydra.send("\x1b");
+    tydra.wait_for_exit();
+    std::fs::remove_file(directory.join("touched")).unwrap();
+
+    let args = [&fixture("end_to_end.yml"), "--page", "@recent"];
+    let mut tydra = Tydra::spawn(&args, &directory);
+    tydra.wait_for_text("Recently used");
+    assert!(tydra.screen().contains("[1] Touch a file"));
+
+    // The entry returns to its own page, like when it is picked there.
+    tydra.send("1");
+    tydra.wait_until("the menu to come back", |tydra| {
+        directory.join("touched").exists() && tydra.screen().contains("End to end")
+    });
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_repeats_the_last_entry_with_the_repeat_key() {
+    let directory = test_directory("repeat");
+    let touched = directory.join("touched");
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+
+    // There is nothing to repeat yet.
+    tydra.send(".");
+    tydra.wait_for_text("Nothing has been run yet.");
+
+    tydra.send("t");
+    tydra.wait_until("the menu to come back", |tydra| {
+        touched.exists() && tydra.screen().contains("End to end")
+    });
+    std::fs::remove_file(&touched).unwrap();
+    tydra.send(".");
+    tydra.wait_until("the file to be touched again", |_| touched.exists());
+    tydra.wait_for_text("End to end");
+    tydra.send("\x1b");
+    tydra.wait_for_exit();
+
+    // The last entry is remembered when tydra is started again.
+    std::fs::remove_file(&touched).unwrap();
+    let mut tydra = Tydra::spawn(&[&fixture("end_to_end.yml")], &directory);
+    tydra.wait_for_text("End to end");
+    tydra.send(".");
+    tydra.wait_until("the file to be touched again", |_| touched.exists());
+    tydra.wait_for_text("End to end");
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_copies_snippets_to_the_clipboard() {
+    let directory = test_directory("copy");
+    let args = [&fixture("end_to_end.yml"), "--page", "snippets"];
+    let mut tydra = Tydra::spawn(&args, &directory);
+    tydra.wait_for_text("Snippets");
+
+    tydra.send("c");
+    tydra.wait_for_text("Copied to the clipboard");
+    assert_eq!(tydra.clipboard().as_deref(), Some("kubectl get pods"));
+
+    tydra.send("\x1b");
+    assert!(tydra.wait_for_exit().success());
+}
+
+#[test]
+fn it_prints_typed_snippets_on_exit() {
+    let directory = test_directory("type");
+    let args = [&fixture("end_to_end.yml"), "--page", "snippets"];
+    let (mut tydra, mut output) = Tydra::spawn_capturing_stdout(&args, &directory);
+    tydra.wait_for_text("Snippets");
+
+    tydra.send("y");
+    assert!(tydra.wait_for_exit().success());
+    assert_eq!(read_to_end(&mut output), "git status --short\n");
+}
+
+#[test]
+fn it_reloads_when_theme_files_change() {
+    let directory = test_directory("watch_themes");
+    let action_file = directory.join("actions.yml");
+    let theme_file = directory.join("theme.yml");
+    std::fs::write(&theme_file, "title: {fg: red}\n").unwrap();
+    std::fs::write(
+        &action_file,
+        "global: {theme: theme.yml}\npages:\n  root:\n    title: Watched\n    groups:\n      \
+         - entries: [{shortcut: q, title: Quit}]\n",
+    )
+    .unwrap();
+    let mut tydra = Tydra::spawn(&["--watch", action_file.to_str().unwrap()], &directory);
+    tydra.wait_for_text("Watched");
+
+    std::fs::write(&theme_file, "titel: {fg: red}\n").unwrap();
+    tydra.wait_for_text("Could not reload");
+
+    tydra.send("q");
+    assert!(tydra.wait_for_exit().success());
+}
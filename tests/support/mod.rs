@@ -0,0 +1,268 @@
+//! Harness for running the tydra binary in a pseudo-terminal, sending it key presses and looking
+//! at what it shows on the screen.
+
+mod terminal;
+
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::pty::{openpty, Winsize};
+use nix::sys::signal::{kill, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+pub use self::terminal::Terminal;
+
+/// How long to wait for tydra to show something before giving up.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A running tydra process, attached to a pseudo-terminal.
+pub struct Tydra {
+    child: Child,
+    master: File,
+    terminal: Terminal,
+    /// Set when tydra has closed the terminal, which happens when it exits.
+    closed: bool,
+}
+
+impl Tydra {
+    /// Starts tydra with the arguments in a 80x24 terminal. Commands run by tydra get the
+    /// directory as `TYDRA_TEST_DIR`, to leave files in. The history is kept there too.
+    pub fn spawn(args: &[&str], directory: &Path) -> Tydra {
+        Tydra::spawn_with_size(args, directory, 80, 24)
+    }
+
+    pub fn spawn_with_size(args: &[&str], directory: &Path, width: u16, height: u16) -> Tydra {
+        Tydra::start(args, directory, width, height, None)
+    }
+
+    /// Starts tydra with its stdout going to a pipe instead of the terminal, like when a shell
+    /// captures its output. Returns the other end of the pipe.
+    pub fn spawn_capturing_stdout(args: &[&str], directory: &Path) -> (Tydra, File) {
+        let (read, write) = nix::unistd::pipe().unwrap();
+        let (read, write) = unsafe { (File::from_raw_fd(read), File::from_raw_fd(write)) };
+        let tydra = Tydra::start(args, directory, 80, 24, Some(write));
+        (tydra, read)
+    }
+
+    fn start(
+        args: &[&str],
+        directory: &Path,
+        width: u16,
+        height: u16,
+        stdout: Option<File>,
+    ) -> Tydra {
+        let size = Winsize {
+            ws_row: height,
+            ws_col: width,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let pty = openpty(Some(&size), None).expect("Could not open a pseudo-terminal");
+        let master = unsafe { File::from_raw_fd(pty.master) };
+        let slave = unsafe { File::from_raw_fd(pty.slave) };
+
+        let capturing_stdout = stdout.is_some();
+        let stdout = stdout.unwrap_or_else(|| slave.try_clone().unwrap());
+
+        let mut command = tydra_command(args, directory);
+        command
+            .stdin(Stdio::from(slave.try_clone().unwrap()))
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(slave));
+        unsafe {
+            if capturing_stdout {
+                // Without stdout, tydra finds the terminal through /dev/tty, which only works in
+                // a session that has the terminal as its controlling terminal.
+                command.pre_exec(|| {
+                    nix::unistd::setsid().map_err(to_io_error)?;
+                    set_controlling_terminal(0, 0).map_err(to_io_error)?;
+                    Ok(())
+                });
+            } else {
+                // A process group of its own, like a job started by a shell. It stays in the
+                // session of the tests so that it can be suspended.
+                command.pre_exec(|| {
+                    nix::unistd::setpgid(Pid::from_raw(0), Pid::from_raw(0)).map_err(to_io_error)
+                });
+            }
+        }
+        let child = command.spawn().expect("Could not start tydra");
+        // The command keeps the slave side open until it is dropped, which would hide that tydra
+        // has exited.
+        drop(command);
+
+        Tydra {
+            child,
+            master,
+            terminal: Terminal::new(width, height),
+            closed: false,
+        }
+    }
+
+    fn pid(&self) -> Pid {
+        Pid::from_raw(self.child.id() as i32)
+    }
+
+    /// Types text, or keys written as escape sequences, like "\x1b" for Esc.
+    pub fn send(&mut self, keys: &str) {
+        self.master.write_all(keys.as_bytes()).unwrap();
+    }
+
+    /// What is on the screen right now.
+    pub fn screen(&mut self) -> String {
+        self.read_output(Duration::from_millis(0));
+        self.terminal.text()
+    }
+
+    pub fn is_alternate_screen(&mut self) -> bool {
+        self.read_output(Duration::from_millis(0));
+        self.terminal.is_alternate_screen()
+    }
+
+    /// What tydra put on the clipboard through the terminal.
+    pub fn clipboard(&mut self) -> Option<String> {
+        self.read_output(Duration::from_millis(0));
+        self.terminal.clipboard().map(String::from)
+    }
+
+    /// Waits until the text is shown on the screen.
+    pub fn wait_for_text(&mut self, text: &str) {
+        self.wait_until(&format!("{:?} to be shown", text), |tydra| {
+            tydra.terminal.text().contains(text)
+        });
+    }
+
+    /// Waits until the condition is true, reading what tydra shows in the meantime. Panics with
+    /// the contents of the screen when it takes too long.
+    pub fn wait_until<F>(&mut self, description: &str, mut condition: F)
+    where
+        F: FnMut(&mut Tydra) -> bool,
+    {
+        let deadline = Instant::now() + TIMEOUT;
+        while !condition(self) {
+            if Instant::now() > deadline {
+                panic!(
+                    "Timed out waiting for {}. The screen shows:\n{}",
+                    description,
+                    self.terminal.text()
+                );
+            }
+            self.read_output(Duration::from_millis(20));
+        }
+    }
+
+    /// Waits for tydra to exit and returns its exit status.
+    pub fn wait_for_exit(&mut self) -> ExitStatus {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            // Keep reading, so that tydra does not block on writing to a full terminal.
+            self.read_output(Duration::from_millis(20));
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            if Instant::now() > deadline {
+                self.child.kill().ok();
+                panic!(
+                    "Timed out waiting for tydra to exit. The screen shows:\n{}",
+                    self.terminal.text()
+                );
+            }
+        }
+    }
+
+    /// Waits until tydra has suspended itself, and then lets it continue.
+    pub fn wait_for_suspend_and_continue(&mut self) {
+        let status = waitpid(self.pid(), Some(WaitPidFlag::WUNTRACED)).unwrap();
+        assert_eq!(status, WaitStatus::Stopped(self.pid(), Signal::SIGTSTP));
+        kill(self.pid(), Signal::SIGCONT).unwrap();
+    }
+
+    /// Changes the size of the terminal, and tells tydra about it like the terminal would.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        let size = Winsize {
+            ws_row: height,
+            ws_col: width,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        unsafe { set_window_size(self.master.as_raw_fd(), &size) }.unwrap();
+        self.terminal.resize(width, height);
+        kill(self.pid(), Signal::SIGWINCH).unwrap();
+    }
+
+    /// Reads everything that tydra has shown, waiting up to the timeout for something to read.
+    fn read_output(&mut self, timeout: Duration) {
+        let mut timeout = timeout.as_millis() as i32;
+        while !self.closed {
+            let mut fds = [PollFd::new(self.master.as_raw_fd(), PollFlags::POLLIN)];
+            if poll(&mut fds, timeout).unwrap() == 0 {
+                return;
+            }
+            // Only wait for the first bit of output, and then read what is already there.
+            timeout = 0;
+
+            let mut buffer = [0; 4096];
+            match self.master.read(&mut buffer) {
+                // Reading fails once the other side of the terminal has been closed.
+                Ok(0) | Err(_) => self.closed = true,
+                Ok(length) => {
+                    self.terminal.feed(&buffer[..length]);
+                    let responses = self.terminal.take_responses();
+                    self.master.write_all(&responses).unwrap();
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Tydra {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            self.child.kill().ok();
+            self.child.wait().ok();
+        }
+    }
+}
+
+nix::ioctl_write_ptr_bad!(set_window_size, nix::libc::TIOCSWINSZ, Winsize);
+nix::ioctl_write_int_bad!(set_controlling_terminal, nix::libc::TIOCSCTTY);
+
+fn to_io_error(error: nix::Error) -> std::io::Error {
+    std::io::Error::other(error)
+}
+
+/// A command that runs tydra with the environment of the tests, without a terminal.
+pub fn tydra_command(args: &[&str], directory: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_tydra"));
+    command
+        .args(args)
+        .env("TERM", "xterm")
+        .env("TYDRA_TEST_DIR", directory)
+        .env("XDG_STATE_HOME", directory)
+        .env_remove("NO_COLOR")
+        // Only the terminal is used for the clipboard, and not the one of the desktop.
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY");
+    command
+}
+
+/// Makes an empty directory for a test to keep its files in.
+pub fn test_directory(name: &str) -> PathBuf {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if directory.exists() {
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Path to a file in `tests/fixtures`.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
@@ -0,0 +1,285 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use unicode_width::UnicodeWidthChar;
+
+const ESC: char = '\x1b';
+
+/// A small terminal emulator that understands the escape sequences that tydra and termion write,
+/// so that tests can look at the screen like a user would.
+///
+/// Colors and text attributes are ignored, as tests only look at the text.
+pub struct Terminal {
+    width: usize,
+    height: usize,
+    normal: Vec<Vec<String>>,
+    alternate: Option<Vec<Vec<String>>>,
+    cursor: (usize, usize),
+    /// Cursor position on the normal screen while the alternate screen is shown.
+    saved_cursor: (usize, usize),
+    /// Output that ends in the middle of an escape sequence or character, to finish later.
+    pending: Vec<u8>,
+    /// Answers to queries from the program, like where the cursor is.
+    responses: Vec<u8>,
+    /// What the program put on the clipboard.
+    clipboard: Option<String>,
+}
+
+impl Terminal {
+    pub fn new(width: u16, height: u16) -> Terminal {
+        let (width, height) = (width as usize, height as usize);
+        Terminal {
+            width,
+            height,
+            normal: blank_lines(width, height),
+            alternate: None,
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            pending: Vec::new(),
+            responses: Vec::new(),
+            clipboard: None,
+        }
+    }
+
+    /// The text on the screen, with the spaces at the end of lines removed.
+    pub fn text(&self) -> String {
+        self.lines()
+            .iter()
+            .map(|line| format!("{}\n", line.concat().trim_end()))
+            .collect()
+    }
+
+    pub fn is_alternate_screen(&self) -> bool {
+        self.alternate.is_some()
+    }
+
+    pub fn clipboard(&self) -> Option<&str> {
+        self.clipboard.as_deref()
+    }
+
+    /// Takes the answers to queries that should be sent back to the program.
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.responses)
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width as usize;
+        self.height = height as usize;
+        for lines in std::iter::once(&mut self.normal).chain(self.alternate.as_mut()) {
+            lines.resize(self.height, Vec::new());
+            for line in lines.iter_mut() {
+                line.resize(self.width, String::from(" "));
+            }
+        }
+        self.cursor.0 = self.cursor.0.min(self.width - 1);
+        self.cursor.1 = self.cursor.1.min(self.height - 1);
+    }
+
+    pub fn feed(&mut self, output: &[u8]) {
+        self.pending.extend_from_slice(output);
+        let pending = std::mem::take(&mut self.pending);
+
+        // Only complete characters are handled; the rest waits for more output.
+        let valid = match std::str::from_utf8(&pending) {
+            Ok(text) => text,
+            Err(error) => std::str::from_utf8(&pending[..error.valid_up_to()]).unwrap(),
+        };
+
+        let mut chars = valid.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            if c == ESC {
+                let rest = &valid[index..];
+                match self.escape_sequence(rest) {
+                    Some(length) => {
+                        while chars.peek().is_some_and(|&(next, _)| next < index + length) {
+                            chars.next();
+                        }
+                    }
+                    None => {
+                        self.pending = pending[index..].to_vec();
+                        return;
+                    }
+                }
+            } else {
+                self.print(c);
+            }
+        }
+        self.pending = pending[valid.len()..].to_vec();
+    }
+
+    fn lines(&self) -> &Vec<Vec<String>> {
+        self.alternate.as_ref().unwrap_or(&self.normal)
+    }
+
+    fn lines_mut(&mut self) -> &mut Vec<Vec<String>> {
+        self.alternate.as_mut().unwrap_or(&mut self.normal)
+    }
+
+    fn print(&mut self, c: char) {
+        match c {
+            '\r' => self.cursor.0 = 0,
+            '\n' => self.line_feed(),
+            '\x08' => self.cursor.0 = self.cursor.0.saturating_sub(1),
+            c if c.is_control() => {}
+            c => {
+                let width = c.width().unwrap_or(0);
+                if width == 0 {
+                    return;
+                }
+                if self.cursor.0 + width > self.width {
+                    self.cursor.0 = 0;
+                    self.line_feed();
+                }
+                let (x, y) = self.cursor;
+                let line = &mut self.lines_mut()[y];
+                line[x] = c.to_string();
+                if width == 2 {
+                    line[x + 1] = String::new();
+                }
+                self.cursor.0 += width;
+            }
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.1 + 1 < self.height {
+            self.cursor.1 += 1;
+        } else {
+            let width = self.width;
+            let lines = self.lines_mut();
+            lines.remove(0);
+            lines.push(blank_line(width));
+        }
+    }
+
+    /// Handles the escape sequence at the start of the text, and returns how many bytes it is.
+    /// Returns None if the sequence is not complete yet.
+    fn escape_sequence(&mut self, text: &str) -> Option<usize> {
+        let bytes = text.as_bytes();
+        match bytes.get(1)? {
+            b'[' => {
+                let end = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b))? + 2;
+                self.control_sequence(&text[2..end], bytes[end] as char);
+                Some(end + 1)
+            }
+            // Operating system commands, which end with BEL or ESC \.
+            b']' => {
+                let end = bytes.iter().position(|&b| b == b'\x07' || b == b'\\')?;
+                self.operating_system_command(text[2..end].trim_end_matches(ESC));
+                Some(end + 1)
+            }
+            // Character set selection, which has one more byte.
+            b'(' | b')' => bytes.get(2).map(|_| 3),
+            _ => Some(2),
+        }
+    }
+
+    fn control_sequence(&mut self, parameters: &str, command: char) {
+        let private = parameters.starts_with('?');
+        let numbers: Vec<usize> = parameters
+            .trim_start_matches('?')
+            .split(';')
+            .map(|number| number.parse().unwrap_or(0))
+            .collect();
+        let number = |index: usize, default: usize| match numbers.get(index) {
+            Some(0) | None => default,
+            Some(&number) => number,
+        };
+
+        match command {
+            'H' | 'f' => {
+                self.cursor = (
+                    (number(1, 1) - 1).min(self.width - 1),
+                    (number(0, 1) - 1).min(self.height - 1),
+                )
+            }
+            'A' => self.cursor.1 = self.cursor.1.saturating_sub(number(0, 1)),
+            'B' => self.cursor.1 = (self.cursor.1 + number(0, 1)).min(self.height - 1),
+            'C' => self.cursor.0 = (self.cursor.0 + number(0, 1)).min(self.width - 1),
+            'D' => self.cursor.0 = self.cursor.0.saturating_sub(number(0, 1)),
+            'G' => self.cursor.0 = (number(0, 1) - 1).min(self.width - 1),
+            'J' => self.erase_display(number(0, 0)),
+            'K' => self.erase_line(number(0, 0)),
+            'n' if number(0, 0) == 6 => {
+                let report = format!("\x1b[{};{}R", self.cursor.1 + 1, self.cursor.0 + 1);
+                self.responses.extend_from_slice(report.as_bytes());
+            }
+            'h' if private && numbers.contains(&1049) => {
+                self.saved_cursor = self.cursor;
+                self.alternate = Some(blank_lines(self.width, self.height));
+            }
+            'l' if private && numbers.contains(&1049) => {
+                self.alternate = None;
+                self.cursor = self.saved_cursor;
+            }
+            _ => {}
+        }
+    }
+
+    fn operating_system_command(&mut self, command: &str) {
+        if let Some(data) = command.strip_prefix("52;c;") {
+            let text = BASE64
+                .decode(data)
+                .expect("Clipboard contents are not base64");
+            self.clipboard = Some(String::from_utf8(text).unwrap());
+        }
+    }
+
+    fn erase_display(&mut self, mode: usize) {
+        let (x, y) = self.cursor;
+        let width = self.width;
+        let lines = self.lines_mut();
+        let range = match mode {
+            0 => {
+                lines[y][x..]
+                    .iter_mut()
+                    .for_each(|cell| *cell = String::from(" "));
+                y + 1..lines.len()
+            }
+            1 => {
+                lines[y][..=x]
+                    .iter_mut()
+                    .for_each(|cell| *cell = String::from(" "));
+                0..y
+            }
+            _ => 0..lines.len(),
+        };
+        for line in &mut lines[range] {
+            *line = blank_line(width);
+        }
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let (x, y) = self.cursor;
+        let line = &mut self.lines_mut()[y];
+        let cells = match mode {
+            0 => &mut line[x..],
+            1 => &mut line[..=x],
+            _ => &mut line[..],
+        };
+        cells.iter_mut().for_each(|cell| *cell = String::from(" "));
+    }
+}
+
+fn blank_line(width: usize) -> Vec<String> {
+    vec![String::from(" "); width]
+}
+
+fn blank_lines(width: usize, height: usize) -> Vec<Vec<String>> {
+    vec![blank_line(width); height]
+}
+
+#[test]
+fn it_moves_the_cursor_and_erases() {
+    let mut terminal = Terminal::new(10, 3);
+    terminal.feed(b"hello\r\nworld\x1b[1;3Hy\x1b[2;2H\x1b[K");
+    assert_eq!(terminal.text(), "heylo\nw\n\n");
+
+    terminal.feed(b"\x1b[?1049h\x1b[3;1Halt\x1b");
+    assert!(terminal.is_alternate_screen());
+    terminal.feed(b"[?1049l");
+    assert_eq!(terminal.text(), "heylo\nw\n\n");
+
+    terminal.feed(b"\x1b]52;c;aGk=\x07");
+    assert_eq!(terminal.clipboard(), Some("hi"));
+    assert_eq!(terminal.text(), "heylo\nw\n\n");
+}